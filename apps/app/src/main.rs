@@ -3,8 +3,40 @@
     windows_subsystem = "windows"
 )]
 
+use japtools_core::{
+    grading::{grade, AnswerType, Leniency, Verdict},
+    mnemonic::{References, RenderedMnemonic},
+    schema::SubjectDataOuter,
+};
+
+// Converts the review input value after each keystroke, unfinished romaji is left as typed
+#[tauri::command]
+fn to_kana(input: &str, ime_mode: bool) -> String {
+    japtools_core::ime::to_kana(input, ime_mode)
+}
+
+#[tauri::command]
+fn grade_answer(subject: SubjectDataOuter, answer_type: AnswerType, answer: &str) -> Verdict {
+    grade(&subject.data, answer_type, answer, &Leniency::default())
+}
+
+// Tags naming one of the given subjects, usually the subject and its components, link to it
+#[tauri::command]
+fn render_mnemonic(
+    text: &str,
+    subjects: Vec<SubjectDataOuter>,
+) -> Result<RenderedMnemonic, String> {
+    japtools_core::mnemonic::render_mnemonic(text, &References::from_subjects(&subjects))
+        .map_err(|e| e.to_string())
+}
+
 fn main() {
     tauri::Builder::default()
+        .invoke_handler(tauri::generate_handler![
+            to_kana,
+            grade_answer,
+            render_mnemonic
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -13,27 +13,41 @@ export const POST: RequestHandler = async ({ request }) => {
 		})
 	);
 	const unique_readings = Array.from(new Set(readings));
+	const reading_keys = unique_readings.map((v) => v.trim().toLowerCase());
 	const query_result = (
 		await prisma_client.subjectIndex.findMany({
 			where: {
-				readings: {
-					hasSome: unique_readings
-				}
+				OR: [
+					{
+						readings: {
+							hasSome: unique_readings
+						}
+					},
+					{
+						reading_keys: {
+							hasSome: reading_keys
+						}
+					}
+				]
 			}
 		})
 	).map((v) => ({
 		id: v.subjectId,
 		readings: v.readings,
+		reading_keys: v.reading_keys,
 		meanings: v.meanings
 	}));
 	const subjects_map: Record<string, { readings: string[]; meanings: string[]; id: number }[]> =
 		typed_from_entries(unique_readings.map((v) => [v, []]));
-	for (const { id, readings, meanings } of query_result) {
+	for (const { id, readings, reading_keys, meanings } of query_result) {
 		if (!meanings) {
 			console.log('no meanings', id);
 		}
-		for (const reading of readings) {
-			if (!subjects_map[reading]) {
+		for (const reading of unique_readings) {
+			if (
+				!readings.includes(reading) &&
+				!reading_keys.includes(reading.trim().toLowerCase())
+			) {
 				continue;
 			}
 			subjects_map[reading].push({ readings, meanings, id });
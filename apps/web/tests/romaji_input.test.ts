@@ -0,0 +1,24 @@
+// Runs WanaKana over the table the Rust romaji engine is tested against, so both agree
+import assert from 'node:assert/strict';
+import { readFileSync } from 'node:fs';
+import { test } from 'node:test';
+import { toKana } from 'wanakana';
+
+type Case = { input: string; output: string; ime_mode?: boolean };
+
+const table: Case[] = JSON.parse(
+	readFileSync(
+		new URL('../../../packages/japtools-core/tests/fixtures/romaji_input.json', import.meta.url),
+		'utf8'
+	)
+);
+
+test('wanakana converts the shared romaji table', () => {
+	for (const { input, output, ime_mode } of table) {
+		assert.equal(
+			toKana(input, { IMEMode: ime_mode ?? false }),
+			output,
+			`${input} (ime mode ${ime_mode ?? false})`
+		);
+	}
+});
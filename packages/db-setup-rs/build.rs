@@ -0,0 +1,85 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+use sha2::{Digest, Sha256};
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{:02x}", byte);
+            out
+        })
+}
+
+// Written into the merged schema by `cargo prisma merge`
+const BASE_HASH_PREFIX: &str = "// base schema sha256 ";
+
+// Embeds the checked-in migrations so dbsetup can compare them with _prisma_migrations
+fn main() {
+    let prisma_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../prisma");
+    let migrations_dir = prisma_dir.join("migrations");
+    println!("cargo:rerun-if-changed={}", migrations_dir.display());
+    println!(
+        "cargo:rerun-if-changed={}",
+        prisma_dir.join("schema.prisma").display()
+    );
+    println!(
+        "cargo:rerun-if-changed={}",
+        prisma_dir.join("rsschema.prisma").display()
+    );
+
+    let mut migrations = fs::read_dir(&migrations_dir)
+        .expect("prisma/migrations must exist")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let script = fs::read(path.join("migration.sql")).ok()?;
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((name, script))
+        })
+        .collect::<Vec<_>>();
+    migrations.sort();
+
+    let schema =
+        fs::read(prisma_dir.join("schema.prisma")).expect("prisma/schema.prisma must exist");
+
+    let mut out = String::new();
+    writeln!(out, "pub const MIGRATIONS: &[EmbeddedMigration] = &[").unwrap();
+    for (name, script) in &migrations {
+        // Prisma accepts a checksum of either line ending style
+        let normalized = String::from_utf8_lossy(script).replace("\r\n", "\n");
+        writeln!(
+            out,
+            "    EmbeddedMigration {{ name: {:?}, checksum: {:?}, checksum_lf: {:?} }},",
+            name,
+            sha256_hex(script),
+            sha256_hex(normalized.as_bytes())
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(
+        out,
+        "pub const SCHEMA_HASH: &str = {:?};",
+        sha256_hex(&schema)
+    )
+    .unwrap();
+
+    // The merged schema db.rs is generated from remembers which schema.prisma it came from
+    let generated = fs::read_to_string(prisma_dir.join("rsschema.prisma"))
+        .ok()
+        .and_then(|merged| {
+            merged
+                .lines()
+                .find_map(|line| line.strip_prefix(BASE_HASH_PREFIX))
+                .map(|hash| hash.trim().to_string())
+        });
+    writeln!(
+        out,
+        "pub const GENERATED_SCHEMA_HASH: Option<&str> = {:?};",
+        generated
+    )
+    .unwrap();
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("migrations.rs"), out).unwrap();
+}
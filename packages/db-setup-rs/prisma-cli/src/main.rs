@@ -1,3 +1,75 @@
+use std::{
+    error::Error,
+    path::PathBuf,
+    process::{self, Command},
+};
+
+mod merge;
+
+const USAGE: &str =
+    "usage: cargo prisma merge [-b base.prisma] [-s extra.prisma]... [-o merged.prisma]";
+
+struct MergeArgs {
+    base: PathBuf,
+    schemas: Vec<PathBuf>,
+    output: PathBuf,
+}
+
+fn parse_merge_args(mut args: impl Iterator<Item = String>) -> Result<MergeArgs, Box<dyn Error>> {
+    let mut base = None;
+    let mut schemas = Vec::new();
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))?;
+        match arg.as_str() {
+            "-b" | "--base" => base = Some(PathBuf::from(value)),
+            "-s" | "--schema" => schemas.push(PathBuf::from(value)),
+            "-o" | "--output" => output = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE).into()),
+        }
+    }
+    if schemas.is_empty() {
+        schemas.push(PathBuf::from("prisma/rsclient.prisma"));
+    }
+    Ok(MergeArgs {
+        base: base.unwrap_or_else(|| PathBuf::from("prisma/schema.prisma")),
+        schemas,
+        output: output.unwrap_or_else(|| PathBuf::from("prisma/rsschema.prisma")),
+    })
+}
+
+// Merges the schemas, then runs the regular generate on the result
+fn merge_and_generate(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let args = parse_merge_args(args)?;
+    let schemas = args
+        .schemas
+        .iter()
+        .map(PathBuf::as_path)
+        .collect::<Vec<_>>();
+    merge::merge_files(&args.base, &schemas, &args.output)?;
+    println!("Merged schema written to {}", args.output.display());
+
+    let status = Command::new(std::env::current_exe()?)
+        .arg("generate")
+        .arg("--schema")
+        .arg(&args.output)
+        .status()?;
+    if !status.success() {
+        return Err(format!("prisma generate failed with {}", status).into());
+    }
+    Ok(())
+}
+
 fn main() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("merge") {
+        if let Err(e) = merge_and_generate(args) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
     prisma_client_rust_cli::run();
 }
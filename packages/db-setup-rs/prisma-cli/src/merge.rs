@@ -0,0 +1,249 @@
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+use sha2::{Digest, Sha256};
+
+// dbsetup's build script reads this back to tell whether db.rs is stale
+const BASE_HASH_PREFIX: &str = "// base schema sha256 ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub kind: String,
+    pub name: String,
+    // Comments directly above the block are kept with it
+    pub text: String,
+}
+
+fn brace_delta(line: &str) -> i32 {
+    let mut delta = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '/' if chars.peek() == Some(&'/') => break,
+            '{' => delta += 1,
+            '}' => delta -= 1,
+            _ => {}
+        }
+    }
+    delta
+}
+
+pub fn parse_blocks(source: &str, origin: &str) -> Result<Vec<Block>, Box<dyn Error>> {
+    let mut blocks = Vec::new();
+    let mut comments = String::new();
+    let mut lines = source.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            comments.clear();
+            continue;
+        }
+        if trimmed.starts_with("//") {
+            comments.push_str(line.trim_end());
+            comments.push('\n');
+            continue;
+        }
+
+        let header = trimmed.split_whitespace().collect::<Vec<_>>();
+        let (kind, name) = match header.as_slice() {
+            [kind, name, "{", ..] => (kind.to_string(), name.to_string()),
+            [kind, name] if name.ends_with('{') => {
+                (kind.to_string(), name.trim_end_matches('{').to_string())
+            }
+            _ => {
+                return Err(format!(
+                    "{}:{}: expected a block like `model Name {{`, found `{}`",
+                    origin,
+                    number + 1,
+                    trimmed
+                )
+                .into())
+            }
+        };
+
+        let mut text = std::mem::take(&mut comments);
+        text.push_str(line.trim_end());
+        text.push('\n');
+        let mut depth = brace_delta(line);
+        while depth > 0 {
+            let (_, line) = lines
+                .next()
+                .ok_or_else(|| format!("{}: {} {} is never closed", origin, kind, name))?;
+            depth += brace_delta(line);
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        blocks.push(Block { kind, name, text });
+    }
+
+    Ok(blocks)
+}
+
+// Later schemas win when a block with the same kind and name already exists. The base
+// schema's generators are dropped, the merged schema only generates the clients added to it
+pub fn merge_blocks(schemas: Vec<Vec<Block>>) -> Result<Vec<Block>, Box<dyn Error>> {
+    let mut merged: Vec<Block> = Vec::new();
+    let blocks = schemas
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, blocks)| blocks.into_iter().map(move |block| (i, block)))
+        .filter(|(i, block)| *i > 0 || block.kind != "generator")
+        .map(|(_, block)| block);
+    for block in blocks {
+        match merged
+            .iter_mut()
+            .find(|x| x.kind == block.kind && x.name == block.name)
+        {
+            Some(existing) => *existing = block,
+            None => merged.push(block),
+        }
+    }
+
+    let datasources = merged.iter().filter(|x| x.kind == "datasource").count();
+    if datasources != 1 {
+        return Err(format!("expected exactly one datasource, found {}", datasources).into());
+    }
+
+    // Prisma does not care about the order, but datasource and generators first reads better
+    let rank = |block: &Block| match block.kind.as_str() {
+        "datasource" => 0,
+        "generator" => 1,
+        _ => 2,
+    };
+    merged.sort_by_key(rank);
+    Ok(merged)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{:02x}", byte);
+            out
+        })
+}
+
+pub fn render_blocks(blocks: &[Block], sources: &[&Path], base: &str) -> String {
+    let mut out = format!(
+        "// Generated by `cargo prisma merge` from {}, do not edit\n{}{}\n",
+        sources
+            .iter()
+            .map(|x| x.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        BASE_HASH_PREFIX,
+        sha256_hex(base.as_bytes())
+    );
+    for block in blocks {
+        out.push('\n');
+        out.push_str(&block.text);
+    }
+    out
+}
+
+pub fn merge_files(base: &Path, schemas: &[&Path], output: &Path) -> Result<(), Box<dyn Error>> {
+    let sources = std::iter::once(base)
+        .chain(schemas.iter().copied())
+        .collect::<Vec<_>>();
+    let mut texts = Vec::new();
+    let mut parsed = Vec::new();
+    for path in &sources {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        parsed.push(parse_blocks(&source, &path.display().to_string())?);
+        texts.push(source);
+    }
+    let merged = merge_blocks(parsed)?;
+    fs::write(output, render_blocks(&merged, &sources, &texts[0]))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"datasource db {
+  provider = "cockroachdb"
+  url      = env("DATABASE_URL")
+}
+
+generator jsclient {
+  provider = "prisma-client-js"
+}
+
+// Kept with the model
+model User {
+  id String @id // { not a block
+}
+
+enum Role {
+  USER
+}
+"#;
+
+    const CLIENT: &str = r#"generator rsclient {
+  provider = "cargo prisma"
+  output   = "../packages/db-setup-rs/src/db.rs"
+}
+
+enum Role {
+  USER
+  ADMIN
+}
+"#;
+
+    fn names(blocks: &[Block]) -> Vec<String> {
+        blocks
+            .iter()
+            .map(|x| format!("{} {}", x.kind, x.name))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_only_added_generators() {
+        let merged = merge_blocks(vec![
+            parse_blocks(BASE, "base").unwrap(),
+            parse_blocks(CLIENT, "client").unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(
+            names(&merged),
+            [
+                "datasource db",
+                "generator rsclient",
+                "model User",
+                "enum Role"
+            ]
+        );
+        assert!(merged[2].text.starts_with("// Kept with the model\n"));
+        assert!(merged[3].text.contains("ADMIN"));
+    }
+
+    #[test]
+    fn drops_base_generators_without_replacement() {
+        let merged = merge_blocks(vec![parse_blocks(BASE, "base").unwrap()]).unwrap();
+        assert!(merged.iter().all(|x| x.kind != "generator"));
+    }
+
+    #[test]
+    fn requires_one_datasource() {
+        assert!(merge_blocks(vec![parse_blocks(CLIENT, "client").unwrap()]).is_err());
+        assert!(merge_blocks(vec![
+            parse_blocks(BASE, "base").unwrap(),
+            parse_blocks(&BASE.replace("db {", "other {"), "other").unwrap(),
+        ])
+        .is_err());
+    }
+}
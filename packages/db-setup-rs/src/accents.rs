@@ -0,0 +1,157 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use itertools::Itertools;
+use japtools_core::{
+    kana::to_hiragana,
+    schema::{SubjectData, SubjectDataOuter},
+};
+use tracing::{info, warn};
+
+const EXAMPLES: usize = 10;
+
+// Accents look like 0, 1,0 or (名)0,(副)1, the part of speech notes are dropped
+fn parse_accents(field: &str) -> Vec<u32> {
+    field
+        .split(',')
+        .filter_map(|accent| {
+            let accent = match accent.rsplit_once(')') {
+                Some((_, accent)) => accent,
+                None => accent,
+            };
+            accent.trim().parse().ok()
+        })
+        .unique()
+        .collect()
+}
+
+// Kanjium accents.txt lines are word, reading and accents separated by tabs,
+// the reading is left empty for words written in kana
+pub fn read_accents(tsv: &str) -> HashMap<(String, String), Vec<u32>> {
+    let mut accents: HashMap<(String, String), Vec<u32>> = HashMap::new();
+    for line in tsv.lines().filter(|x| !x.starts_with('#')) {
+        let (word, reading, field) = match line.split('\t').collect_vec().as_slice() {
+            [word, reading, field, ..] => (*word, *reading, *field),
+            _ => continue,
+        };
+        let reading = if reading.is_empty() { word } else { reading };
+        let entry = accents
+            .entry((word.to_string(), to_hiragana(reading)))
+            .or_default();
+        for accent in parse_accents(field) {
+            if !entry.contains(&accent) {
+                entry.push(accent);
+            }
+        }
+    }
+    accents.retain(|_, x| !x.is_empty());
+    accents
+}
+
+pub async fn read_accent_file(
+    path: &Path,
+) -> Result<HashMap<(String, String), Vec<u32>>, Box<dyn Error>> {
+    let tsv = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    Ok(read_accents(&tsv))
+}
+
+pub fn attach_accents(
+    map: &mut HashMap<u32, SubjectDataOuter>,
+    accents: &HashMap<(String, String), Vec<u32>>,
+) {
+    let mut matched = 0;
+    let mut unmatched = Vec::new();
+    for subject in map.values_mut().sorted_by_key(|x| x.id) {
+        if let SubjectData::Vocabulary(data) = &mut subject.data {
+            let mut found = false;
+            for reading in &mut data.readings {
+                let key = (data.characters.clone(), to_hiragana(&reading.reading));
+                if let Some(downsteps) = accents.get(&key) {
+                    reading.pitch_accents = downsteps.clone();
+                    found = true;
+                }
+            }
+            if found {
+                matched += 1;
+            } else {
+                unmatched.push(format!("{} ({})", data.characters, subject.id));
+            }
+        }
+    }
+    info!(
+        matched,
+        unmatched = unmatched.len(),
+        "attached pitch accents"
+    );
+    if !unmatched.is_empty() {
+        warn!(
+            examples = %unmatched.iter().take(EXAMPLES).join(", "),
+            "vocabulary without pitch accent data"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/accents.txt");
+
+    fn key(word: &str, reading: &str) -> (String, String) {
+        (word.to_string(), reading.to_string())
+    }
+
+    fn subject(json: &str) -> (u32, SubjectDataOuter) {
+        let subject: SubjectDataOuter = serde_json::from_str(json).unwrap();
+        (subject.id, subject)
+    }
+
+    #[test]
+    fn parses_accent_fields() {
+        assert_eq!(parse_accents("0"), [0]);
+        assert_eq!(parse_accents("1,0"), [1, 0]);
+        assert_eq!(parse_accents("(名)0,(副)1,0"), [0, 1]);
+        assert!(parse_accents("x").is_empty());
+    }
+
+    #[test]
+    fn reads_accents_by_word_and_reading() {
+        let accents = read_accents(FIXTURE);
+        // Comments, rows without accents and rows missing a field are skipped
+        assert_eq!(accents.len(), 5);
+        // Katakana readings are keyed in hiragana and rows for the same reading merge
+        assert_eq!(accents[&key("一", "いち")], [2, 0]);
+        assert_eq!(accents[&key("大人", "おとな")], [0]);
+        assert_eq!(accents[&key("大人", "だいにん")], [1, 0]);
+        // Kana words have no reading column and are keyed by the word itself
+        assert_eq!(accents[&key("あれ", "あれ")], [0]);
+        assert_eq!(accents[&key("オレンジ", "おれんじ")], [2]);
+    }
+
+    #[test]
+    fn attaches_accents_to_vocabulary_readings() {
+        let mut map = HashMap::from([
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/vocabulary.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/vocabulary_readings.json"
+            )),
+        ]);
+        attach_accents(&mut map, &read_accents(FIXTURE));
+        let accents = |id| match &map[&id].data {
+            SubjectData::Vocabulary(data) => data
+                .readings
+                .iter()
+                .map(|x| (x.reading.as_str(), x.pitch_accents.clone()))
+                .collect_vec(),
+            _ => panic!("{} is not vocabulary", id),
+        };
+        assert_eq!(accents(2467), [("いち", vec![2, 0])]);
+        assert_eq!(
+            accents(2560),
+            [("おとな", vec![0]), ("だいにん", vec![1, 0])]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::{num::NonZeroUsize, ops::RangeInclusive, path::PathBuf, str::FromStr};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    loader::{CACHE_FILE, MAX_LEVEL},
+    source::{JMDICT_FILE, KANJIDIC_FILE},
+    telemetry::LogFormat,
+};
+
+pub const EXIT_FINDINGS: u8 = 1;
+pub const EXIT_FAILURE: u8 = 3;
+pub const EXIT_SCHEMA_DRIFT: u8 = 4;
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  validate found problems or diff found changes
+  2  invalid arguments
+  3  runtime failure (network, database, io)
+  4  database migrations do not match this build";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "dbsetup",
+    about = "Loads WaniKani subjects into the japtools databases",
+    after_help = EXIT_CODES
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// Log output format, json disables the progress bars
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct LevelRange(pub RangeInclusive<u32>);
+
+impl FromStr for LevelRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid level '{}'", x))
+        };
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if start < 1 || end > MAX_LEVEL || start > end {
+            return Err(format!(
+                "level range must be within 1-{} and ascending",
+                MAX_LEVEL
+            ));
+        }
+        Ok(LevelRange(start..=end))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    /// Path of the cached WaniKani subject map
+    #[arg(long, default_value = CACHE_FILE)]
+    pub cache: PathBuf,
+    /// A single level or an inclusive range like 1-10
+    #[arg(long, default_value = "1-60")]
+    pub levels: LevelRange,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    Wanikani,
+    Kanjidic,
+    Jmdict,
+}
+
+#[derive(Args, Debug)]
+pub struct SourceArgs {
+    /// Where subjects come from, repeat to combine several sources
+    #[arg(long = "source", value_enum, default_values_t = [SourceKind::Wanikani])]
+    pub sources: Vec<SourceKind>,
+    /// Path of a KANJIDIC2 XML file
+    #[arg(long, default_value = KANJIDIC_FILE)]
+    pub kanjidic: PathBuf,
+    /// Path of a JMdict XML file
+    #[arg(long, default_value = JMDICT_FILE)]
+    pub jmdict: PathBuf,
+    /// Directory of KanjiVG SVG files to take kanji stroke order from
+    #[arg(long)]
+    pub kanjivg: Option<PathBuf>,
+    /// Pitch accent TSV of word, reading and downstep like Kanjium's accents.txt
+    #[arg(long)]
+    pub accents: Option<PathBuf>,
+    /// Tatoeba Japanese-English sentence pairs TSV to pick example sentences from
+    #[arg(long)]
+    pub tatoeba: Option<PathBuf>,
+    /// Tatoeba jpn_indices.csv, links conjugated words to their vocabulary
+    #[arg(long, requires = "tatoeba")]
+    pub tatoeba_indices: Option<PathBuf>,
+    /// Number of example sentences kept per kanji and vocabulary
+    #[arg(long, default_value_t = 5)]
+    pub examples_per_subject: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct DatabaseArgs {
+    /// Overrides the datasource url of the generated client
+    #[arg(long, env = "DATABASE_URL")]
+    pub database_url: Option<String>,
+    /// Number of subjects uploaded in parallel
+    #[arg(long, default_value = "300")]
+    pub concurrency: NonZeroUsize,
+    /// Upload even if the applied migrations differ from prisma/migrations
+    #[arg(long)]
+    pub skip_schema_check: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Sqlite,
+    Seed,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum SchedulerKind {
+    /// WaniKani's fixed stage intervals
+    Fixed,
+    /// FSRS intervals mapped back onto the WaniKani stages
+    Adaptive,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Download subjects from WaniKani into the cache
+    Fetch(CacheArgs),
+    /// Upload cached subjects to the database
+    Upload {
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        sources: SourceArgs,
+        #[command(flatten)]
+        database: DatabaseArgs,
+    },
+    /// Fetch subjects from WaniKani and upload them
+    Sync {
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        sources: SourceArgs,
+        #[command(flatten)]
+        database: DatabaseArgs,
+    },
+    /// Check cached subjects for broken references and missing data
+    Validate {
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        sources: SourceArgs,
+        /// Also report WaniKani fields and values that schema.rs does not know about
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Compare cached subjects against the current WaniKani data
+    Diff(CacheArgs),
+    /// Write cached subjects to an SQLite database or an SQL seed file
+    Export {
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        sources: SourceArgs,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Sqlite)]
+        format: ExportFormat,
+        /// Defaults to target/subjects.sqlite or target/seed.sql
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print subject counts per level
+    Stats {
+        #[command(flatten)]
+        cache: CacheArgs,
+        #[command(flatten)]
+        sources: SourceArgs,
+    },
+    /// Append answers from a JSON file to a user's review log
+    ImportReviews {
+        /// Id of the user the answers belong to
+        #[arg(long)]
+        user: String,
+        /// JSON array of review log entries
+        file: PathBuf,
+        #[command(flatten)]
+        database: DatabaseArgs,
+    },
+    /// Rebuild a user's subject progress from their review log
+    ReplayReviews {
+        /// Id of the user whose progress is rebuilt
+        #[arg(long)]
+        user: String,
+        #[arg(long, value_enum, default_value_t = SchedulerKind::Fixed)]
+        scheduler: SchedulerKind,
+        #[command(flatten)]
+        database: DatabaseArgs,
+    },
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use japtools_core::schema::SubjectDataOuter;
+
+#[derive(Debug, Clone, Default)]
+pub struct SubjectDiff {
+    pub added: Vec<u32>,
+    pub removed: Vec<u32>,
+    pub changed: Vec<u32>,
+}
+
+impl SubjectDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+pub fn diff_subjects(
+    old: &HashMap<u32, SubjectDataOuter>,
+    new: &HashMap<u32, SubjectDataOuter>,
+) -> SubjectDiff {
+    let added = new
+        .keys()
+        .filter(|id| !old.contains_key(id))
+        .copied()
+        .sorted()
+        .collect_vec();
+    let removed = old
+        .keys()
+        .filter(|id| !new.contains_key(id))
+        .copied()
+        .sorted()
+        .collect_vec();
+    let changed = new
+        .iter()
+        .filter_map(|(id, subject)| {
+            let old_subject = old.get(id)?;
+            let differs = old_subject.data_updated_at != subject.data_updated_at
+                || serde_json::to_value(old_subject).ok() != serde_json::to_value(subject).ok();
+            differs.then_some(*id)
+        })
+        .sorted()
+        .collect_vec();
+
+    SubjectDiff {
+        added,
+        removed,
+        changed,
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashMap, error::Error, ops::RangeInclusive};
+
+use itertools::Itertools;
+use japtools_core::schema::{
+    SubjectData, SubjectDataOuter, SubjectMeaning, VocabularyData, VocabularyReading,
+};
+use roxmltree::{Document, Node, NodeType, ParsingOptions, NS_XML_URI};
+use tracing::info;
+
+use crate::source::{dictionary_timestamp, lesson_positions, level_from_rank, JMDICT_ID_OFFSET};
+
+// Only entries ranked in one of the 48 nfXX newspaper frequency bands are imported
+const NF_BANDS: u32 = 48;
+const CREATED_COMMENT: &str = "JMdict created:";
+
+struct Entry {
+    sequence: u32,
+    band: u32,
+    characters: String,
+    kana_only: bool,
+    readings: Vec<String>,
+    meanings: Vec<String>,
+    parts_of_speech: Vec<String>,
+}
+
+fn texts<'a>(node: Node<'a, '_>, tag: &str) -> Vec<&'a str> {
+    node.children()
+        .filter(|x| x.has_tag_name(tag))
+        .filter_map(|x| x.text())
+        .collect()
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>, tag: &'a str) -> Vec<Node<'a, 'input>> {
+    node.children().filter(|x| x.has_tag_name(tag)).collect()
+}
+
+fn nf_band(priorities: &[&str]) -> Option<u32> {
+    priorities
+        .iter()
+        .filter_map(|x| x.strip_prefix("nf")?.parse::<u32>().ok())
+        .min()
+}
+
+fn parse_entry(entry: Node) -> Option<Entry> {
+    let sequence = texts(entry, "ent_seq").first()?.parse::<u32>().ok()?;
+    let kanji = elements(entry, "k_ele");
+    let kana = elements(entry, "r_ele");
+
+    let band = kanji
+        .iter()
+        .map(|x| texts(*x, "ke_pri"))
+        .chain(kana.iter().map(|x| texts(*x, "re_pri")))
+        .filter_map(|x| nf_band(&x))
+        .min()?;
+
+    let kana_only = kanji.is_empty();
+    let characters = match kanji.first() {
+        Some(k_ele) => texts(*k_ele, "keb").first()?.to_string(),
+        None => texts(*kana.first()?, "reb").first()?.to_string(),
+    };
+    // Readings restricted to other spellings or marked as not a kanji reading are dropped
+    let readings = kana
+        .iter()
+        .filter(|r_ele| kana_only || elements(**r_ele, "re_nokanji").is_empty())
+        .filter(|r_ele| {
+            let restrictions = texts(**r_ele, "re_restr");
+            restrictions.is_empty() || restrictions.contains(&characters.as_str())
+        })
+        .filter_map(|r_ele| texts(*r_ele, "reb").first().map(|x| x.to_string()))
+        .unique()
+        .collect_vec();
+
+    let senses = elements(entry, "sense");
+    let meanings = senses
+        .iter()
+        .flat_map(|sense| elements(*sense, "gloss"))
+        .filter(|gloss| matches!(gloss.attribute((NS_XML_URI, "lang")), None | Some("eng")))
+        .filter_map(|gloss| gloss.text())
+        .map(str::to_string)
+        .unique()
+        .collect_vec();
+    let parts_of_speech = senses
+        .iter()
+        .flat_map(|sense| texts(*sense, "pos"))
+        .map(str::to_string)
+        .unique()
+        .collect_vec();
+
+    if readings.is_empty() || meanings.is_empty() {
+        return None;
+    }
+    Some(Entry {
+        sequence,
+        band,
+        characters,
+        kana_only,
+        readings,
+        meanings,
+        parts_of_speech,
+    })
+}
+
+fn subject_meanings(meanings: &[String]) -> Vec<SubjectMeaning> {
+    meanings
+        .iter()
+        .enumerate()
+        .map(|(i, meaning)| SubjectMeaning {
+            accepted_answer: true,
+            meaning: meaning.clone(),
+            primary: i == 0,
+        })
+        .collect()
+}
+
+fn entry_subject(
+    entry: Entry,
+    level: u32,
+    lesson_position: u32,
+    created_at: &str,
+) -> SubjectDataOuter {
+    let id = JMDICT_ID_OFFSET + entry.sequence;
+    let url = format!("https://jisho.org/word/{}", entry.characters);
+    let meanings = subject_meanings(&entry.meanings);
+
+    let data = SubjectData::Vocabulary(VocabularyData {
+        auxiliary_meanings: vec![],
+        characters: entry.characters.clone(),
+        component_subject_ids: vec![],
+        context_sentences: vec![],
+        created_at: created_at.to_string(),
+        document_url: url.clone(),
+        hidden_at: None,
+        lesson_position,
+        level,
+        meaning_mnemonic: String::new(),
+        meanings,
+        parts_of_speech: entry.parts_of_speech,
+        pronunciation_audios: vec![],
+        reading_mnemonic: String::new(),
+        readings: entry
+            .readings
+            .iter()
+            .enumerate()
+            .map(|(i, reading)| VocabularyReading {
+                accepted_answer: true,
+                primary: i == 0,
+                reading: reading.clone(),
+                pitch_accents: vec![],
+                furigana: vec![],
+                composition: None,
+            })
+            .collect(),
+        slug: entry.characters,
+        spaced_repetition_system_id: 1,
+        example_sentences: vec![],
+    });
+
+    SubjectDataOuter {
+        id,
+        url,
+        data_updated_at: created_at.to_string(),
+        data,
+    }
+}
+
+pub fn read_jmdict(
+    xml: &str,
+    levels: &RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let document = Document::parse_with_options(
+        xml,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )?;
+    let created_at = document
+        .root()
+        .children()
+        .filter(|x| x.node_type() == NodeType::Comment)
+        .filter_map(|x| x.text()?.trim().strip_prefix(CREATED_COMMENT))
+        .map(dictionary_timestamp)
+        .next()
+        .unwrap_or_default();
+
+    // Kana only words would be kana_vocabulary, which upload and export have no table for
+    let (kana_only, mut entries): (Vec<_>, Vec<_>) = document
+        .root_element()
+        .children()
+        .filter(|x| x.has_tag_name("entry"))
+        .filter_map(parse_entry)
+        .partition(|x| x.kana_only);
+    if !kana_only.is_empty() {
+        info!(dropped = kana_only.len(), "kana only JMdict entries");
+    }
+    entries.sort_by_key(|x| (x.band, x.sequence));
+
+    let entry_levels = entries
+        .iter()
+        .map(|x| level_from_rank(x.band, NF_BANDS))
+        .collect_vec();
+    let positions = lesson_positions(entry_levels.iter().copied());
+    let subjects = entries
+        .into_iter()
+        .zip(entry_levels.into_iter().zip(positions))
+        .filter(|(_, (level, _))| levels.contains(level))
+        .map(|(entry, (level, position))| entry_subject(entry, level, position, &created_at))
+        .map(|subject| (subject.id, subject))
+        .collect();
+    Ok(subjects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/jmdict.xml");
+
+    fn vocabulary(subjects: &HashMap<u32, SubjectDataOuter>, sequence: u32) -> &VocabularyData {
+        match &subjects[&(JMDICT_ID_OFFSET + sequence)].data {
+            SubjectData::Vocabulary(data) => data,
+            _ => panic!("{} is not vocabulary", sequence),
+        }
+    }
+
+    #[test]
+    fn imports_ranked_kanji_entries() {
+        let subjects = read_jmdict(FIXTURE, &(1..=60)).unwrap();
+        // Unranked and kana only entries are left out
+        assert_eq!(
+            subjects
+                .keys()
+                .map(|x| x - JMDICT_ID_OFFSET)
+                .sorted()
+                .collect_vec(),
+            [1311110, 1358280, 1578850]
+        );
+
+        let student = vocabulary(&subjects, 1311110);
+        assert_eq!(student.level, 1);
+        assert_eq!(student.lesson_position, 0);
+        assert_eq!(student.created_at, "2023-12-01T00:00:00.000000Z");
+
+        let eat = vocabulary(&subjects, 1358280);
+        assert_eq!(eat.level, 12);
+        assert_eq!(eat.meanings.len(), 1);
+        assert_eq!(eat.meanings[0].meaning, "to eat");
+        assert_eq!(eat.parts_of_speech, ["Ichidan verb"]);
+
+        let go = vocabulary(&subjects, 1578850);
+        assert_eq!(go.level, 2);
+        assert_eq!(go.characters, "行く");
+        assert_eq!(
+            go.readings.iter().map(|x| x.reading.as_str()).collect_vec(),
+            ["いく"]
+        );
+        assert_eq!(
+            go.meanings.iter().map(|x| x.meaning.as_str()).collect_vec(),
+            ["to go", "to move"]
+        );
+        assert!(go.meanings[0].primary && !go.meanings[1].primary);
+    }
+
+    #[test]
+    fn filters_levels_after_ranking() {
+        let subjects = read_jmdict(FIXTURE, &(2..=60)).unwrap();
+        assert_eq!(subjects.len(), 2);
+        assert_eq!(vocabulary(&subjects, 1578850).lesson_position, 0);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, error::Error, mem::discriminant, ops::RangeInclusive};
+
+use itertools::Itertools;
+use japtools_core::{
+    kana::to_hiragana,
+    schema::{KanjiData, KanjiReading, ReadingType, SubjectData, SubjectDataOuter, SubjectMeaning},
+};
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::source::{dictionary_timestamp, lesson_positions, level_from_rank, KANJIDIC_ID_OFFSET};
+
+// KANJIDIC2 ranks the 2500 most used kanji in newspapers, everything else is skipped
+const FREQ_RANKS: u32 = 2501;
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|x| x.has_tag_name(tag))
+}
+
+// Kun readings mark okurigana with a dot and affixes with a dash: ひと.つ, -び
+fn kun_stem(reading: &str) -> String {
+    reading
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_matches('-')
+        .to_string()
+}
+
+fn push_reading(readings: &mut Vec<KanjiReading>, reading: KanjiReading) {
+    let duplicate = readings.iter().any(|x| {
+        x.reading == reading.reading
+            && discriminant(&x.reading_type) == discriminant(&reading.reading_type)
+    });
+    if !reading.reading.is_empty() && !duplicate {
+        readings.push(reading);
+    }
+}
+
+fn kanji_readings(character: Node) -> Vec<KanjiReading> {
+    let mut readings = Vec::new();
+    for node in character
+        .descendants()
+        .filter(|x| x.has_tag_name("reading"))
+    {
+        let text = node.text().unwrap_or_default();
+        let (reading, reading_type) = match node.attribute("r_type") {
+            Some("ja_on") => (to_hiragana(text), ReadingType::Onyomi),
+            Some("ja_kun") => (kun_stem(text), ReadingType::Kunyomi),
+            _ => continue,
+        };
+        push_reading(
+            &mut readings,
+            KanjiReading {
+                accepted_answer: true,
+                primary: false,
+                reading,
+                reading_type,
+            },
+        );
+    }
+    for node in character.descendants().filter(|x| x.has_tag_name("nanori")) {
+        push_reading(
+            &mut readings,
+            KanjiReading {
+                accepted_answer: false,
+                primary: false,
+                reading: node.text().unwrap_or_default().to_string(),
+                reading_type: ReadingType::Nanori,
+            },
+        );
+    }
+
+    // Like WaniKani the first on'yomi is primary, kun'yomi only for kanji without one
+    let primary = readings
+        .iter()
+        .position(|x| matches!(x.reading_type, ReadingType::Onyomi))
+        .or_else(|| {
+            readings
+                .iter()
+                .position(|x| matches!(x.reading_type, ReadingType::Kunyomi))
+        });
+    if let Some(primary) = primary {
+        readings[primary].primary = true;
+    }
+    readings
+}
+
+fn kanji_meanings(character: Node) -> Vec<SubjectMeaning> {
+    character
+        .descendants()
+        .filter(|x| x.has_tag_name("meaning"))
+        .filter(|x| x.attribute("m_lang").is_none())
+        .filter_map(|x| x.text())
+        .unique()
+        .enumerate()
+        .map(|(i, meaning)| SubjectMeaning {
+            accepted_answer: true,
+            meaning: meaning.to_string(),
+            primary: i == 0,
+        })
+        .collect()
+}
+
+pub fn read_kanjidic(
+    xml: &str,
+    levels: &RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let document = Document::parse_with_options(
+        xml,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )?;
+    let root = document.root_element();
+    let created_at = child(root, "header")
+        .and_then(|x| child(x, "date_of_creation"))
+        .and_then(|x| x.text())
+        .map(dictionary_timestamp)
+        .unwrap_or_default();
+
+    let mut ranked = Vec::new();
+    for character in root.children().filter(|x| x.has_tag_name("character")) {
+        let literal = child(character, "literal")
+            .and_then(|x| x.text())
+            .ok_or("KANJIDIC2 character without a literal")?;
+        let rank = match child(character, "misc")
+            .and_then(|x| child(x, "freq"))
+            .and_then(|x| x.text())
+            .and_then(|x| x.parse::<u32>().ok())
+        {
+            Some(rank) => rank,
+            None => continue,
+        };
+        let meanings = kanji_meanings(character);
+        let readings = kanji_readings(character);
+        if meanings.is_empty() || readings.is_empty() {
+            continue;
+        }
+        ranked.push((rank, literal, meanings, readings));
+    }
+    ranked.sort_by_key(|(rank, ..)| *rank);
+
+    let positions = lesson_positions(
+        ranked
+            .iter()
+            .map(|(rank, ..)| level_from_rank(*rank, FREQ_RANKS)),
+    );
+    let mut subjects = HashMap::new();
+    for ((rank, literal, meanings, readings), lesson_position) in ranked.into_iter().zip(positions)
+    {
+        let level = level_from_rank(rank, FREQ_RANKS);
+        if !levels.contains(&level) {
+            continue;
+        }
+        let id = KANJIDIC_ID_OFFSET + literal.chars().next().map(u32::from).unwrap_or_default();
+        let url = format!("https://jisho.org/search/{}%20%23kanji", literal);
+        let data = KanjiData {
+            amalgamation_subject_ids: vec![],
+            auxiliary_meanings: vec![],
+            characters: literal.to_string(),
+            component_subject_ids: vec![],
+            created_at: created_at.clone(),
+            document_url: url.clone(),
+            hidden_at: None,
+            lesson_position,
+            level,
+            meaning_hint: None,
+            meaning_mnemonic: String::new(),
+            meanings,
+            reading_hint: String::new(),
+            reading_mnemonic: String::new(),
+            readings,
+            slug: literal.to_string(),
+            spaced_repetition_system_id: 1,
+            visually_similar_subject_ids: vec![],
+            stroke_paths: vec![],
+            example_sentences: vec![],
+        };
+        subjects.insert(
+            id,
+            SubjectDataOuter {
+                id,
+                url,
+                data_updated_at: created_at.clone(),
+                data: SubjectData::Kanji(data),
+            },
+        );
+    }
+    Ok(subjects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/kanjidic2.xml");
+
+    fn kanji(subjects: &HashMap<u32, SubjectDataOuter>, literal: char) -> &KanjiData {
+        match &subjects[&(KANJIDIC_ID_OFFSET + u32::from(literal))].data {
+            SubjectData::Kanji(data) => data,
+            _ => panic!("{} is not a kanji", literal),
+        }
+    }
+
+    fn readings(data: &KanjiData) -> Vec<(&str, ReadingType, bool)> {
+        data.readings
+            .iter()
+            .map(|x| (x.reading.as_str(), x.reading_type, x.primary))
+            .collect()
+    }
+
+    #[test]
+    fn splits_kun_readings_at_the_dot() {
+        assert_eq!(kun_stem("ひと.つ"), "ひと");
+        assert_eq!(kun_stem("ひと-"), "ひと");
+        assert_eq!(kun_stem("-こ.む"), "こ");
+        assert_eq!(kun_stem("-び"), "び");
+        assert_eq!(kun_stem("ひ"), "ひ");
+    }
+
+    #[test]
+    fn imports_ranked_kanji() {
+        let subjects = read_kanjidic(FIXTURE, &(1..=60)).unwrap();
+        // 丐 has no newspaper frequency rank
+        assert_eq!(
+            subjects.keys().sorted().collect_vec(),
+            [&10_019_968, &10_026_085, &10_036_796]
+        );
+
+        let sun = kanji(&subjects, '日');
+        assert_eq!((sun.level, sun.lesson_position), (1, 0));
+        assert_eq!(sun.created_at, "2023-12-01T00:00:00.000000Z");
+        assert_eq!(
+            sun.meanings
+                .iter()
+                .map(|x| x.meaning.as_str())
+                .collect_vec(),
+            ["day", "sun", "Japan"]
+        );
+
+        let one = kanji(&subjects, '一');
+        assert_eq!((one.level, one.lesson_position), (1, 1));
+        // French meanings are left out
+        assert_eq!(one.meanings.len(), 1);
+        assert_eq!(
+            readings(one),
+            [
+                ("いち", ReadingType::Onyomi, true),
+                ("いつ", ReadingType::Onyomi, false),
+                ("ひと", ReadingType::Kunyomi, false),
+                ("かず", ReadingType::Nanori, false),
+                ("い", ReadingType::Nanori, false),
+            ]
+        );
+        // Name readings are shown but not accepted
+        assert!(one
+            .readings
+            .iter()
+            .all(|x| x.accepted_answer != (x.reading_type == ReadingType::Nanori)));
+
+        // Without an on'yomi the first kun'yomi is primary
+        let crowded = kanji(&subjects, '込');
+        assert_eq!(crowded.level, 44);
+        assert_eq!(readings(crowded), [("こ", ReadingType::Kunyomi, true)]);
+    }
+
+    #[test]
+    fn filters_levels_after_ranking() {
+        let subjects = read_kanjidic(FIXTURE, &(2..=60)).unwrap();
+        assert_eq!(subjects.len(), 1);
+        assert_eq!(kanji(&subjects, '込').lesson_position, 0);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use japtools_core::schema::{SubjectData, SubjectDataOuter};
+use roxmltree::{Document, ParsingOptions};
+use tracing::info;
+
+// KanjiVG names files after the code point, variants carry a suffix: 04e00.svg, 04e00-Kaisho.svg
+fn file_character(stem: &str) -> Option<String> {
+    if stem.contains('-') {
+        return None;
+    }
+    let code_point = u32::from_str_radix(stem, 16).ok()?;
+    char::from_u32(code_point).map(String::from)
+}
+
+// Stroke ids end in their position: kvg:04e00-s1
+fn stroke_number(id: &str) -> Option<u32> {
+    id.rsplit_once("-s")?.1.parse().ok()
+}
+
+const KVG_NAMESPACE: &str = "http://kanjivg.tagaini.net";
+
+pub fn read_strokes(svg: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    // The kvg prefix is only declared through #FIXED attributes in the DTD, which roxmltree ignores
+    let svg = if svg.contains("xmlns:kvg=\"") {
+        svg.to_string()
+    } else {
+        svg.replacen(
+            "<svg ",
+            &format!("<svg xmlns:kvg=\"{}\" ", KVG_NAMESPACE),
+            1,
+        )
+    };
+    let document = Document::parse_with_options(
+        &svg,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )?;
+    // The StrokeNumbers group only holds the labels, the paths live under StrokePaths
+    let group = document
+        .descendants()
+        .find(|x| {
+            x.has_tag_name("g")
+                && x.attribute("id")
+                    .is_some_and(|id| id.starts_with("kvg:StrokePaths"))
+        })
+        .ok_or("no StrokePaths group")?;
+    let mut strokes = group
+        .descendants()
+        .filter(|x| x.has_tag_name("path"))
+        .filter_map(|x| {
+            let number = x.attribute("id").and_then(stroke_number)?;
+            Some((number, x.attribute("d")?.to_string()))
+        })
+        .collect::<Vec<_>>();
+    strokes.sort_by_key(|(number, _)| *number);
+    Ok(strokes.into_iter().map(|(_, path)| path).collect())
+}
+
+pub async fn read_kanjivg(dir: &Path) -> Result<HashMap<String, Vec<String>>, Box<dyn Error>> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| format!("could not read {}: {}", dir.display(), e))?;
+    let mut strokes = HashMap::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|x| x.to_str()) != Some("svg") {
+            continue;
+        }
+        let character = match path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .and_then(file_character)
+        {
+            Some(character) => character,
+            None => continue,
+        };
+        let svg = tokio::fs::read_to_string(&path).await?;
+        let paths = read_strokes(&svg).map_err(|e| format!("{}: {}", path.display(), e))?;
+        strokes.insert(character, paths);
+    }
+    Ok(strokes)
+}
+
+pub fn attach_strokes(
+    map: &mut HashMap<u32, SubjectDataOuter>,
+    strokes: &HashMap<String, Vec<String>>,
+) {
+    let mut attached = 0;
+    let mut missing = 0;
+    for subject in map.values_mut() {
+        if let SubjectData::Kanji(data) = &mut subject.data {
+            match strokes.get(&data.characters) {
+                Some(paths) => {
+                    data.stroke_paths = paths.clone();
+                    attached += 1;
+                }
+                None => missing += 1,
+            }
+        }
+    }
+    info!(attached, missing, "attached KanjiVG strokes");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/kanjivg/04e8c.svg");
+
+    fn subject(json: &str) -> (u32, SubjectDataOuter) {
+        let subject: SubjectDataOuter = serde_json::from_str(json).unwrap();
+        (subject.id, subject)
+    }
+
+    fn stroke_paths(subject: &SubjectDataOuter) -> &[String] {
+        match &subject.data {
+            SubjectData::Kanji(data) => &data.stroke_paths,
+            _ => panic!("{} is not a kanji", subject.id),
+        }
+    }
+
+    #[test]
+    fn names_files_after_the_character() {
+        assert_eq!(file_character("04e8c").as_deref(), Some("二"));
+        assert_eq!(file_character("2000b").as_deref(), Some("𠀋"));
+        assert_eq!(file_character("04e8c-Kaisho"), None);
+        assert_eq!(file_character("readme"), None);
+    }
+
+    #[test]
+    fn reads_strokes_in_order() {
+        let strokes = read_strokes(FIXTURE).unwrap();
+        assert_eq!(strokes.len(), 2);
+        assert!(strokes[0].starts_with("M29.5,27.65"));
+        assert!(strokes[1].starts_with("M13.5,77.23"));
+
+        // Nested groups can list a later stroke first, an explicit namespace is kept
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:kvg="http://kanjivg.tagaini.net">
+<g id="kvg:StrokeNumbers_05341"><path id="kvg:05341-s9" d="M0,0"/></g>
+<g id="kvg:StrokePaths_05341">
+<g kvg:element="十"><path id="kvg:05341-s2" d="M2,2"/></g>
+<path id="kvg:05341-s1" d="M1,1"/>
+<path d="M3,3"/>
+</g>
+</svg>"#;
+        assert_eq!(read_strokes(svg).unwrap(), ["M1,1", "M2,2"]);
+        assert!(read_strokes("<svg></svg>").is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_a_directory_without_variants() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/kanjivg");
+        let strokes = read_kanjivg(&dir).await.unwrap();
+        assert_eq!(strokes.keys().collect::<Vec<_>>(), ["二"]);
+        assert_eq!(strokes["二"], read_strokes(FIXTURE).unwrap());
+    }
+
+    #[test]
+    fn attaches_strokes_to_kanji() {
+        let mut map = HashMap::from([
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/kanji.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/kanji_hidden.json"
+            )),
+        ]);
+        let strokes = HashMap::from([("一".to_string(), vec!["M1,1".to_string()])]);
+        attach_strokes(&mut map, &strokes);
+        assert_eq!(stroke_paths(&map[&440]), ["M1,1"]);
+        assert!(stroke_paths(&map[&1005]).is_empty());
+    }
+}
@@ -1,14 +1,16 @@
-use std::{collections::HashMap, env, error::Error, path::Path};
+use std::{collections::HashMap, env, error::Error, ops::RangeInclusive, path::Path};
 
-use indicatif::ProgressBar;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncWriteExt},
 };
+use tracing::{debug, info, info_span, Instrument};
 
-use crate::schema::SubjectDataOuter;
+use japtools_core::schema::SubjectDataOuter;
+
+use crate::telemetry::progress_bar;
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct Pages {
@@ -19,7 +21,7 @@ pub struct Pages {
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct RequestData {
-    data: Vec<SubjectDataOuter>,
+    data: Vec<Value>,
     data_updated_at: String,
     object: String,
     url: String,
@@ -27,57 +29,170 @@ pub struct RequestData {
     pages: Pages,
 }
 
-const CACHE_FILE: &str = "target/wanikani.json";
-
-pub async fn fetch_wanikani_data() -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
-    let client = reqwest::Client::new();
+pub const CACHE_FILE: &str = "target/wanikani.json";
+pub const MAX_LEVEL: u32 = 60;
 
-    let mut values = Vec::new();
-    let progress_bar = ProgressBar::new(60);
-    let wanikani_token = env::var("WANIKANI_TOKEN").expect(
+fn wanikani_token() -> Result<String, Box<dyn Error>> {
+    let wanikani_token = env::var("WANIKANI_TOKEN").map_err(|_| {
         r#"
-            WANIKANI_TOKEN must be set, example for .env (not valid): 
+            WANIKANI_TOKEN must be set, example for .env (not valid):
             WANIKANI_TOKEN = "Bearer d57b2ff1-211f-4f6d-a078-bc01447d0235"
-        "#,
-    );
+        "#
+    })?;
     if !wanikani_token.starts_with("Bearer ") {
-        panic!("WANIKANI_TOKEN must start with 'Bearer '");
+        return Err("WANIKANI_TOKEN must start with 'Bearer '".into());
     }
-    for i in progress_bar.wrap_iter(1..=60) {
-        let resp = client
-            .get(format!("https://api.wanikani.com/v2/subjects?levels={}", i))
-            .header("Authorization", &wanikani_token)
-            .send()
-            .await?;
+    Ok(wanikani_token)
+}
+
+async fn fetch_level(
+    client: &reqwest::Client,
+    wanikani_token: &str,
+    level: u32,
+) -> Result<RequestData, Box<dyn Error>> {
+    let resp = client
+        .get(format!(
+            "https://api.wanikani.com/v2/subjects?levels={}",
+            level
+        ))
+        .header("Authorization", wanikani_token)
+        .send()
+        .await?
+        .error_for_status()?;
 
-        let text = resp.text().await?;
-        let value: RequestData = serde_json::from_str(&text)?;
+    let text = resp.text().await?;
+    let value: RequestData = serde_json::from_str(&text)?;
+    debug!(subjects = value.data.len(), "fetched level");
+    Ok(value)
+}
+
+// Subjects are kept as raw JSON until parsed so fields unknown to schema.rs survive in the cache
+pub async fn fetch_raw_subjects(
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, Value>, Box<dyn Error>> {
+    let client = reqwest::Client::new();
+
+    let mut values = Vec::new();
+    let progress_bar = progress_bar(levels.clone().count() as u64);
+    let wanikani_token = wanikani_token()?;
+    info!(levels = ?levels, "fetching subjects from WaniKani");
+    for level in progress_bar.wrap_iter(levels) {
+        let value = fetch_level(&client, &wanikani_token, level)
+            .instrument(info_span!("fetch_level", level))
+            .await?;
         values.push(value);
     }
 
     progress_bar.finish();
-    let subject_vector = values.iter().flat_map(|x| x.data.clone()).collect_vec();
-    let subject_map = subject_vector
-        .into_iter()
-        .map(|x| (x.id, x))
-        .collect::<HashMap<u32, SubjectDataOuter>>();
+    let mut subject_map = HashMap::new();
+    for subject in values.into_iter().flat_map(|x| x.data) {
+        let id = subject
+            .get("id")
+            .and_then(Value::as_u64)
+            .ok_or("WaniKani returned a subject without an id")?;
+        subject_map.insert(id as u32, subject);
+    }
+
+    Ok(subject_map)
+}
+
+pub async fn fetch_wanikani_data(
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    parse_subjects(fetch_raw_subjects(levels).await?)
+}
+
+pub fn parse_subjects(
+    raw: HashMap<u32, Value>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    raw.into_iter()
+        .map(|(id, value)| {
+            let subject = serde_json::from_value(value).map_err(|e| {
+                format!(
+                    "subject {} does not match schema.rs ({}), run `dbsetup validate --strict`",
+                    id, e
+                )
+            })?;
+            Ok((id, subject))
+        })
+        .collect()
+}
+
+fn raw_level(subject: &Value) -> Option<u32> {
+    subject
+        .pointer("/data/level")
+        .and_then(Value::as_u64)
+        .map(|level| level as u32)
+}
 
-    let mut file = File::create(CACHE_FILE).await?;
-    file.write_all(serde_json::to_string(&subject_map).unwrap().as_bytes())
+pub async fn write_cache(
+    cache: &Path,
+    subject_map: &HashMap<u32, Value>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = cache.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = File::create(cache).await?;
+    file.write_all(serde_json::to_string(subject_map)?.as_bytes())
         .await?;
+    Ok(())
+}
 
+pub async fn read_raw_cache(cache: &Path) -> Result<HashMap<u32, Value>, Box<dyn Error>> {
+    let mut file = File::open(cache).await?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await?;
+    let subject_map: HashMap<u32, Value> = serde_json::from_str(&contents)?;
     Ok(subject_map)
 }
 
-pub async fn load_wanikani_data() -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
-    let filename = CACHE_FILE;
-    if Path::new(filename).exists() {
-        let mut file = File::open(filename).await?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).await?;
-        let subject_map: HashMap<u32, SubjectDataOuter> = serde_json::from_str(&contents)?;
-        Ok(subject_map)
-    } else {
-        fetch_wanikani_data().await
+pub async fn read_cache(cache: &Path) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    parse_subjects(read_raw_cache(cache).await?)
+}
+
+pub fn filter_levels(
+    subject_map: HashMap<u32, SubjectDataOuter>,
+    levels: &RangeInclusive<u32>,
+) -> HashMap<u32, SubjectDataOuter> {
+    subject_map
+        .into_iter()
+        .filter(|(_, subject)| match subject.data.level() {
+            Some(level) => levels.contains(&level),
+            None => true,
+        })
+        .collect()
+}
+
+pub async fn load_raw_data(cache: &Path) -> Result<HashMap<u32, Value>, Box<dyn Error>> {
+    if cache.exists() {
+        return read_raw_cache(cache).await;
     }
+    let subject_map = fetch_raw_subjects(1..=MAX_LEVEL).await?;
+    write_cache(cache, &subject_map).await?;
+    Ok(subject_map)
+}
+
+pub async fn load_wanikani_data(
+    cache: &Path,
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let subject_map = parse_subjects(load_raw_data(cache).await?)?;
+    Ok(filter_levels(subject_map, &levels))
+}
+
+// Replaces the cached subjects of the given levels with freshly fetched ones
+pub async fn refresh_cache(
+    cache: &Path,
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let mut subject_map = if cache.exists() {
+        read_raw_cache(cache).await?
+    } else {
+        HashMap::new()
+    };
+    subject_map
+        .retain(|_, subject| !matches!(raw_level(subject), Some(level) if levels.contains(&level)));
+    subject_map.extend(fetch_raw_subjects(levels.clone()).await?);
+    write_cache(cache, &subject_map).await?;
+    Ok(filter_levels(parse_subjects(subject_map)?, &levels))
 }
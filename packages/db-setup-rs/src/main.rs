@@ -1,16 +1,275 @@
-use loader::load_wanikani_data;
-use std::error::Error;
+use accents::{attach_accents, read_accent_file};
+use clap::Parser;
+use cli::{
+    CacheArgs, Cli, Command, DatabaseArgs, ExportFormat, SchedulerKind, SourceArgs, SourceKind,
+    EXIT_FAILURE, EXIT_FINDINGS, EXIT_SCHEMA_DRIFT,
+};
+use diff::diff_subjects;
+use itertools::Itertools;
+use japtools_core::{
+    schema::SubjectDataOuter,
+    srs::{Fsrs, Scheduler, SpacedRepetitionSystem},
+};
+use kanjivg::{attach_strokes, read_kanjivg};
+use loader::{
+    fetch_wanikani_data, filter_levels, load_raw_data, read_cache, refresh_cache, MAX_LEVEL,
+};
+use preflight::{connect, SchemaDrift};
+use readings::analyze_readings;
+use reviews::{append_reviews, read_review_file, replay_progress};
+use seed::write_seed;
+use sentences::tokenize_sentences;
+use source::{load_sources, JmdictSource, KanjidicSource, SubjectSource, WaniKaniSource};
+use sqlite::write_sqlite;
+use stats::{print_stats, subject_stats};
+use std::{
+    collections::HashMap, error::Error, ops::RangeInclusive, path::PathBuf, process::ExitCode,
+    time::Instant,
+};
+use strict::{print_report, strict_check};
+use tatoeba::{attach_examples, read_tatoeba};
+use tracing::{error, info, warn};
 use upload::upload_to_db;
+use validate::validate;
 
+mod accents;
+mod cli;
 mod db;
+mod diff;
+mod jmdict;
+mod kanjidic;
+mod kanjivg;
 mod loader;
-mod schema;
+mod preflight;
+mod readings;
+mod reviews;
+mod rows;
+mod seed;
+mod sentences;
+mod source;
+mod sqlite;
+mod stats;
+mod strict;
+mod tatoeba;
+mod telemetry;
 mod upload;
+mod validate;
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let map = load_wanikani_data().await?;
-    //upload_to_db(map).await?;
+const SQLITE_FILE: &str = "target/subjects.sqlite";
+const SEED_FILE: &str = "target/seed.sql";
+
+async fn upload(
+    map: HashMap<u32, SubjectDataOuter>,
+    database: DatabaseArgs,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let report = upload_to_db(
+        map,
+        database.database_url,
+        database.concurrency.get(),
+        database.skip_schema_check,
+    )
+    .await?;
+    info!(
+        uploaded = report.uploaded,
+        failed = report.failed,
+        "upload finished"
+    );
+    if report.failed > 0 {
+        return Ok(ExitCode::from(EXIT_FAILURE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn load_subjects(
+    cache: &CacheArgs,
+    args: &SourceArgs,
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let sources = args
+        .sources
+        .iter()
+        .unique()
+        .map(|kind| -> Box<dyn SubjectSource> {
+            match kind {
+                SourceKind::Wanikani => Box::new(WaniKaniSource {
+                    cache: cache.cache.clone(),
+                }),
+                SourceKind::Kanjidic => Box::new(KanjidicSource {
+                    path: args.kanjidic.clone(),
+                }),
+                SourceKind::Jmdict => Box::new(JmdictSource {
+                    path: args.jmdict.clone(),
+                }),
+            }
+        })
+        .collect_vec();
+    let mut map = load_sources(&sources, levels).await?;
+    if let Some(dir) = &args.kanjivg {
+        attach_strokes(&mut map, &read_kanjivg(dir).await?);
+    }
+    if let Some(path) = &args.accents {
+        attach_accents(&mut map, &read_accent_file(path).await?);
+    }
+    analyze_readings(&mut map);
+    tokenize_sentences(&mut map);
+    if let Some(pairs) = &args.tatoeba {
+        let sentences = read_tatoeba(pairs, args.tatoeba_indices.as_deref()).await?;
+        attach_examples(&mut map, &sentences, args.examples_per_subject);
+    }
+    Ok(map)
+}
+
+async fn run(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    match command {
+        Command::Fetch(CacheArgs { cache, levels }) => {
+            let map = refresh_cache(&cache, levels.0).await?;
+            info!(subjects = map.len(), cache = %cache.display(), "fetched subjects");
+        }
+        Command::Upload {
+            cache,
+            sources,
+            database,
+        } => {
+            let map = load_subjects(&cache, &sources, cache.levels.0.clone()).await?;
+            return upload(map, database).await;
+        }
+        Command::Sync {
+            cache,
+            sources,
+            database,
+        } => {
+            // Goes through the same sources as upload, which replaces every subject row
+            refresh_cache(&cache.cache, cache.levels.0.clone()).await?;
+            let map = load_subjects(&cache, &sources, cache.levels.0.clone()).await?;
+            return upload(map, database).await;
+        }
+        Command::Validate {
+            cache,
+            sources,
+            strict,
+        } => {
+            let mut findings = 0;
+            if strict && sources.sources.contains(&SourceKind::Wanikani) {
+                let raw = load_raw_data(&cache.cache).await?;
+                let report = strict_check(&raw, &cache.levels.0);
+                print_report(&report);
+                findings += report.findings();
+            }
+            let map = match load_subjects(&cache, &sources, 1..=MAX_LEVEL).await {
+                Ok(map) => map,
+                Err(e) if strict => {
+                    warn!(error = %e, "skipping reference checks");
+                    info!(problems = findings, "validation failed");
+                    return Ok(ExitCode::from(EXIT_FINDINGS));
+                }
+                Err(e) => return Err(e),
+            };
+            let problems = validate(&map, &cache.levels.0);
+            for problem in &problems {
+                println!("subject {}: {}", problem.subject_id, problem.message);
+            }
+            findings += problems.len();
+            if findings > 0 {
+                info!(problems = findings, "validation failed");
+                return Ok(ExitCode::from(EXIT_FINDINGS));
+            }
+            info!("no problems found");
+        }
+        Command::Diff(CacheArgs { cache, levels }) => {
+            let cached = filter_levels(read_cache(&cache).await?, &levels.0);
+            let fetched = fetch_wanikani_data(levels.0).await?;
+            let diff = diff_subjects(&cached, &fetched);
+            for (label, ids) in [
+                ("added", &diff.added),
+                ("removed", &diff.removed),
+                ("changed", &diff.changed),
+            ] {
+                for id in ids {
+                    println!("{:<8} {}", label, id);
+                }
+            }
+            if !diff.is_empty() {
+                info!(
+                    added = diff.added.len(),
+                    removed = diff.removed.len(),
+                    changed = diff.changed.len(),
+                    "cache differs from WaniKani"
+                );
+                return Ok(ExitCode::from(EXIT_FINDINGS));
+            }
+            info!("cache is up to date");
+        }
+        Command::Export {
+            cache,
+            sources,
+            format,
+            output,
+        } => {
+            let map = load_subjects(&cache, &sources, cache.levels.0.clone()).await?;
+            match format {
+                ExportFormat::Sqlite => {
+                    let output = output.unwrap_or_else(|| PathBuf::from(SQLITE_FILE));
+                    write_sqlite(&map, &output)?;
+                    info!(output = %output.display(), "export written");
+                }
+                ExportFormat::Seed => {
+                    let output = output.unwrap_or_else(|| PathBuf::from(SEED_FILE));
+                    write_seed(&map, &output)?;
+                    info!(output = %output.display(), "export written");
+                }
+            }
+        }
+        Command::Stats { cache, sources } => {
+            let map = load_subjects(&cache, &sources, cache.levels.0.clone()).await?;
+            print_stats(&subject_stats(&map));
+        }
+        Command::ImportReviews {
+            user,
+            file,
+            database,
+        } => {
+            let entries = read_review_file(&file).await?;
+            let client = connect(database.database_url, database.skip_schema_check).await?;
+            let appended = append_reviews(&client, &user, &entries).await?;
+            info!(user = %user, appended, "reviews imported");
+        }
+        Command::ReplayReviews {
+            user,
+            scheduler,
+            database,
+        } => {
+            let system = SpacedRepetitionSystem::wanikani();
+            let scheduler = match scheduler {
+                SchedulerKind::Fixed => Scheduler::Fixed(system),
+                SchedulerKind::Adaptive => Scheduler::Adaptive {
+                    model: Fsrs::default(),
+                    system,
+                },
+            };
+            let client = connect(database.database_url, database.skip_schema_check).await?;
+            let subjects = replay_progress(&client, &user, &scheduler).await?;
+            info!(user = %user, subjects, "progress replayed");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-    Ok(())
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let timings = telemetry::init(cli.log_format);
+    let started = Instant::now();
+    let code = match run(cli.command).await {
+        Ok(code) => code,
+        Err(e) if e.is::<SchemaDrift>() => {
+            error!("{}", e);
+            ExitCode::from(EXIT_SCHEMA_DRIFT)
+        }
+        Err(e) => {
+            error!(error = %e, "dbsetup failed");
+            ExitCode::from(EXIT_FAILURE)
+        }
+    };
+    telemetry::log_summary(&timings, started);
+    code
 }
@@ -0,0 +1,268 @@
+use std::{error::Error, fmt};
+
+use prisma_client_rust::raw;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::db;
+
+pub struct EmbeddedMigration {
+    pub name: &'static str,
+    pub checksum: &'static str,
+    pub checksum_lf: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AppliedMigration {
+    pub migration_name: String,
+    pub checksum: String,
+    pub finished_at: Option<Value>,
+    pub rolled_back_at: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDrift {
+    pub pending: Vec<String>,
+    pub unknown: Vec<String>,
+    pub modified: Vec<String>,
+    pub failed: Vec<String>,
+    // Hash of the schema.prisma db.rs was generated from, when it is not the current one
+    pub stale_client: Option<String>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+            && self.unknown.is_empty()
+            && self.modified.is_empty()
+            && self.failed.is_empty()
+            && self.stale_client.is_none()
+    }
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "database schema does not match the generated client (schema {})",
+            &SCHEMA_HASH[..12]
+        )?;
+        for (marker, label, names) in [
+            ("+", "not applied to the database", &self.pending),
+            ("-", "applied but unknown to this build", &self.unknown),
+            ("~", "changed after being applied", &self.modified),
+            ("!", "failed or rolled back", &self.failed),
+        ] {
+            for name in names {
+                writeln!(f, "  {} {} ({})", marker, name, label)?;
+            }
+        }
+        if let Some(hash) = &self.stale_client {
+            writeln!(
+                f,
+                "  ! db.rs was generated from schema {}, prisma/schema.prisma changed since",
+                &hash[..12.min(hash.len())]
+            )?;
+        }
+        write!(
+            f,
+            "run `prisma migrate deploy` or regenerate db.rs, or pass --skip-schema-check"
+        )
+    }
+}
+
+impl Error for SchemaDrift {}
+
+pub fn compare_migrations(applied: &[AppliedMigration]) -> SchemaDrift {
+    let mut drift = SchemaDrift::default();
+    let finished = applied
+        .iter()
+        .filter(|x| x.rolled_back_at.is_none() && x.finished_at.is_some())
+        .collect::<Vec<_>>();
+    // A rolled back attempt is fine once the migration was applied again
+    for migration in applied {
+        if (migration.rolled_back_at.is_some() || migration.finished_at.is_none())
+            && !finished
+                .iter()
+                .any(|x| x.migration_name == migration.migration_name)
+            && !drift.failed.contains(&migration.migration_name)
+        {
+            drift.failed.push(migration.migration_name.clone());
+        }
+    }
+    let applied = finished;
+
+    for embedded in MIGRATIONS {
+        match applied.iter().find(|x| x.migration_name == embedded.name) {
+            None => drift.pending.push(embedded.name.to_string()),
+            Some(migration) => {
+                if migration.checksum != embedded.checksum
+                    && migration.checksum != embedded.checksum_lf
+                {
+                    drift.modified.push(embedded.name.to_string());
+                }
+            }
+        }
+    }
+    for migration in applied {
+        if !MIGRATIONS
+            .iter()
+            .any(|x| x.name == migration.migration_name)
+        {
+            drift.unknown.push(migration.migration_name.clone());
+        }
+    }
+    drift
+}
+
+// The schema hash db.rs was generated from, when it is not the one this build embeds
+fn stale_client(generated: Option<&str>) -> Option<String> {
+    match generated {
+        Some(hash) if hash != SCHEMA_HASH => Some(hash.to_string()),
+        Some(_) => None,
+        None => {
+            warn!("prisma/rsschema.prisma has no schema hash, cannot check db.rs");
+            None
+        }
+    }
+}
+
+pub async fn check_schema(client: &db::PrismaClient) -> Result<(), Box<dyn Error>> {
+    let applied: Vec<AppliedMigration> = client
+        ._query_raw(raw!(
+            r#"SELECT "migration_name", "checksum", "finished_at", "rolled_back_at"
+               FROM "_prisma_migrations" ORDER BY "started_at""#
+        ))
+        .exec()
+        .await
+        .map_err(|e| {
+            format!(
+                "could not read _prisma_migrations, is the database migrated? ({})",
+                e
+            )
+        })?;
+
+    let mut drift = compare_migrations(&applied);
+    drift.stale_client = stale_client(GENERATED_SCHEMA_HASH);
+    if !drift.is_empty() {
+        return Err(Box::new(drift));
+    }
+    info!(
+        migrations = MIGRATIONS.len(),
+        schema = &SCHEMA_HASH[..12],
+        "database schema matches"
+    );
+    Ok(())
+}
+
+// Builds the client and refuses to continue if the database is not migrated to this build
+pub async fn connect(
+    database_url: Option<String>,
+    skip_schema_check: bool,
+) -> Result<db::PrismaClient, Box<dyn Error>> {
+    let builder = match database_url {
+        Some(url) => db::PrismaClient::_builder().with_url(url),
+        None => db::PrismaClient::_builder(),
+    };
+    let client = builder.build().await?;
+    if skip_schema_check {
+        warn!("skipping database schema check");
+    } else {
+        check_schema(&client).await?;
+    }
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn applied(name: &str, checksum: &str) -> AppliedMigration {
+        AppliedMigration {
+            migration_name: name.to_string(),
+            checksum: checksum.to_string(),
+            finished_at: Some(json!("2023-12-08T10:15:45.000Z")),
+            rolled_back_at: None,
+        }
+    }
+
+    fn all_applied() -> Vec<AppliedMigration> {
+        MIGRATIONS
+            .iter()
+            .map(|x| applied(x.name, x.checksum))
+            .collect()
+    }
+
+    #[test]
+    fn matches_when_every_migration_is_applied() {
+        assert!(compare_migrations(&all_applied()).is_empty());
+
+        // Prisma may have hashed the script with LF line endings
+        let mut migrations = all_applied();
+        migrations[0].checksum = MIGRATIONS[0].checksum_lf.to_string();
+        assert!(compare_migrations(&migrations).is_empty());
+    }
+
+    #[test]
+    fn reports_pending_and_unknown_migrations() {
+        let mut migrations = all_applied();
+        let last = migrations.pop().unwrap();
+        migrations.push(applied("20990101000000_from_another_branch", "0"));
+        let drift = compare_migrations(&migrations);
+        assert_eq!(drift.pending, [last.migration_name]);
+        assert_eq!(drift.unknown, ["20990101000000_from_another_branch"]);
+        assert!(drift.modified.is_empty() && drift.failed.is_empty());
+        let report = drift.to_string();
+        assert!(report.contains(&format!(
+            "+ {} (not applied",
+            MIGRATIONS[MIGRATIONS.len() - 1].name
+        )));
+        assert!(report.contains("- 20990101000000_from_another_branch (applied but unknown"));
+    }
+
+    #[test]
+    fn reports_modified_migrations() {
+        let mut migrations = all_applied();
+        migrations[1].checksum = "0".repeat(64);
+        let drift = compare_migrations(&migrations);
+        assert_eq!(drift.modified, [MIGRATIONS[1].name]);
+        assert!(drift.pending.is_empty() && drift.unknown.is_empty());
+    }
+
+    #[test]
+    fn reports_failed_migrations_until_applied_again() {
+        let mut migrations = all_applied();
+        let name = MIGRATIONS[2].name;
+        migrations[2].finished_at = None;
+        let drift = compare_migrations(&migrations);
+        assert_eq!(drift.failed, [name]);
+        assert_eq!(drift.pending, [name]);
+
+        // Resolved as rolled back and deployed again
+        migrations[2].rolled_back_at = Some(json!("2023-12-08T10:20:00.000Z"));
+        migrations.push(applied(name, MIGRATIONS[2].checksum));
+        assert!(compare_migrations(&migrations).is_empty());
+    }
+
+    #[test]
+    fn reports_a_client_generated_from_another_schema() {
+        assert_eq!(stale_client(Some(SCHEMA_HASH)), None);
+        assert_eq!(stale_client(None), None);
+        let hash = "a".repeat(64);
+        assert_eq!(stale_client(Some(&hash)), Some(hash.clone()));
+
+        let drift = SchemaDrift {
+            stale_client: Some(hash),
+            ..SchemaDrift::default()
+        };
+        assert!(!drift.is_empty());
+        assert!(drift
+            .to_string()
+            .contains("db.rs was generated from schema aaaaaaaaaaaa"));
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use japtools_core::{
+    furigana::analyze_reading,
+    schema::{KanjiReading, SubjectData, SubjectDataOuter},
+};
+use tracing::info;
+
+fn kanji_readings(map: &HashMap<u32, SubjectDataOuter>) -> HashMap<u32, (char, Vec<KanjiReading>)> {
+    map.values()
+        .filter_map(|subject| match &subject.data {
+            SubjectData::Kanji(data) => {
+                let character = data.characters.chars().next()?;
+                Some((subject.id, (character, data.readings.clone())))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Vocabulary whose component kanji are outside the loaded levels is left unanalyzed
+// instead of being marked irregular
+pub fn analyze_readings(map: &mut HashMap<u32, SubjectDataOuter>) {
+    let kanji = kanji_readings(map);
+    let mut analyzed = 0;
+    let mut skipped = 0;
+    for subject in map.values_mut() {
+        let data = match &mut subject.data {
+            SubjectData::Vocabulary(data) => data,
+            _ => continue,
+        };
+        let components = data
+            .component_subject_ids
+            .iter()
+            .map(|id| kanji.get(id).cloned())
+            .collect::<Option<HashMap<char, Vec<KanjiReading>>>>();
+        let components = match components {
+            Some(components) => components,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        for reading in &mut data.readings {
+            match analyze_reading(&data.characters, &reading.reading, &components) {
+                Some(analysis) => {
+                    reading.furigana = analysis.furigana;
+                    reading.composition = Some(analysis.composition);
+                    analyzed += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+    }
+    info!(analyzed, skipped, "analyzed vocabulary readings");
+}
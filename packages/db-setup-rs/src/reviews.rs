@@ -0,0 +1,203 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use chrono::{DateTime, TimeZone, Utc};
+use itertools::Itertools;
+use prisma_client_rust::QueryError;
+use tracing::{info, warn};
+
+use japtools_core::{
+    grading::{AnswerType, Verdict},
+    review_log::{replay_history, ReviewLogEntry},
+    schema::ReadingType,
+    srs::Scheduler,
+};
+
+use crate::db;
+
+fn reading_type_to_db(reading_type: ReadingType) -> db::ReadingType {
+    match reading_type {
+        ReadingType::Onyomi => db::ReadingType::Onyomi,
+        ReadingType::Kunyomi => db::ReadingType::Kunyomi,
+        ReadingType::Nanori => db::ReadingType::Nanori,
+    }
+}
+
+fn reading_type_from_db(reading_type: db::ReadingType) -> ReadingType {
+    match reading_type {
+        db::ReadingType::Onyomi => ReadingType::Onyomi,
+        db::ReadingType::Kunyomi => ReadingType::Kunyomi,
+        db::ReadingType::Nanori => ReadingType::Nanori,
+    }
+}
+
+fn verdict_to_db(verdict: Verdict) -> (db::ReviewVerdict, Option<db::ReadingType>) {
+    match verdict {
+        Verdict::Correct => (db::ReviewVerdict::Correct, None),
+        Verdict::Close => (db::ReviewVerdict::Close, None),
+        Verdict::OtherReading => (db::ReviewVerdict::OtherReading, None),
+        Verdict::WrongReadingType { expected } => (
+            db::ReviewVerdict::WrongReadingType,
+            Some(reading_type_to_db(expected)),
+        ),
+        Verdict::Blacklisted => (db::ReviewVerdict::Blacklisted, None),
+        Verdict::Wrong => (db::ReviewVerdict::Wrong, None),
+    }
+}
+
+fn verdict_from_db(verdict: db::ReviewVerdict, expected: Option<db::ReadingType>) -> Verdict {
+    match (verdict, expected) {
+        (db::ReviewVerdict::Correct, _) => Verdict::Correct,
+        (db::ReviewVerdict::Close, _) => Verdict::Close,
+        (db::ReviewVerdict::OtherReading, _) => Verdict::OtherReading,
+        (db::ReviewVerdict::WrongReadingType, Some(expected)) => Verdict::WrongReadingType {
+            expected: reading_type_from_db(expected),
+        },
+        // Without the expected type the answer can only count as a plain miss
+        (db::ReviewVerdict::WrongReadingType, None) => Verdict::Wrong,
+        (db::ReviewVerdict::Blacklisted, _) => Verdict::Blacklisted,
+        (db::ReviewVerdict::Wrong, _) => Verdict::Wrong,
+    }
+}
+
+fn entry_from_row(row: db::review_log::Data) -> ReviewLogEntry {
+    ReviewLogEntry {
+        subject_id: row.subject_id as u32,
+        reviewed_at: row.reviewed_at.with_timezone(&Utc),
+        answer_type: match row.answer_type {
+            db::AnswerType::Meaning => AnswerType::Meaning,
+            db::AnswerType::Reading => AnswerType::Reading,
+        },
+        given_answer: row.given_answer,
+        verdict: verdict_from_db(row.verdict, row.expected_reading_type),
+        previous_level: row.previous_level as u32,
+        new_level: row.new_level as u32,
+        response_time_ms: row.response_time_ms as u32,
+    }
+}
+
+pub async fn read_review_file(path: &Path) -> Result<Vec<ReviewLogEntry>, Box<dyn Error>> {
+    let json = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub async fn append_reviews(
+    client: &db::PrismaClient,
+    user_id: &str,
+    entries: &[ReviewLogEntry],
+) -> Result<i64, QueryError> {
+    client
+        .review_log()
+        .create_many(
+            entries
+                .iter()
+                .map(|entry| {
+                    let (verdict, expected_reading_type) = verdict_to_db(entry.verdict);
+                    db::review_log::create_unchecked(
+                        user_id.to_string(),
+                        entry.subject_id as i32,
+                        match entry.answer_type {
+                            AnswerType::Meaning => db::AnswerType::Meaning,
+                            AnswerType::Reading => db::AnswerType::Reading,
+                        },
+                        entry.given_answer.clone(),
+                        verdict,
+                        entry.previous_level as i32,
+                        entry.new_level as i32,
+                        entry.response_time_ms as i32,
+                        vec![
+                            db::review_log::reviewed_at::set(entry.reviewed_at.into()),
+                            db::review_log::expected_reading_type::set(expected_reading_type),
+                        ],
+                    )
+                })
+                .collect(),
+        )
+        .exec()
+        .await
+}
+
+// Burned subjects never come up again, but next_review can't be null
+fn burned_review() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(9999, 12, 31, 0, 0, 0).unwrap()
+}
+
+// Rewrites the user's subject progress from their review log, returns the number of subjects
+pub async fn replay_progress(
+    client: &db::PrismaClient,
+    user_id: &str,
+    scheduler: &Scheduler,
+) -> Result<usize, Box<dyn Error>> {
+    let user = client
+        .user()
+        .find_unique(db::user::id::equals(user_id.to_string()))
+        .exec()
+        .await?
+        .ok_or_else(|| format!("user {} not found", user_id))?;
+    let entries = client
+        .review_log()
+        .find_many(vec![db::review_log::user_id::equals(user_id.to_string())])
+        .exec()
+        .await?
+        .into_iter()
+        .map(entry_from_row)
+        .collect_vec();
+    let progress = replay_history(scheduler, &entries);
+    let levels = client
+        .subject_index()
+        .find_many(vec![db::subject_index::subject_id::in_vec(
+            progress.iter().map(|x| x.subject_id as i32).collect(),
+        )])
+        .exec()
+        .await?
+        .into_iter()
+        .map(|x| (x.subject_id, x.level))
+        .collect::<HashMap<_, _>>();
+    info!(
+        answers = entries.len(),
+        subjects = progress.len(),
+        "replaying review log"
+    );
+
+    let mut replayed = 0;
+    for subject in &progress {
+        let level = match levels.get(&(subject.subject_id as i32)) {
+            Some(level) => *level,
+            None => {
+                warn!(subject_id = subject.subject_id, "subject not in the index");
+                continue;
+            }
+        };
+        let next_review = subject
+            .schedule
+            .next_review
+            .unwrap_or_else(burned_review)
+            .into();
+        let params = vec![
+            db::subject_progress::skill_level::set(subject.schedule.stage as i32),
+            db::subject_progress::next_review::set(next_review),
+            db::subject_progress::last_level_change::set(subject.last_level_change.map(Into::into)),
+        ];
+        client
+            .subject_progress()
+            .upsert(
+                db::subject_progress::subject_id_progress_id(
+                    subject.subject_id as i32,
+                    user.progress_id.clone(),
+                ),
+                db::subject_progress::create(
+                    db::subject_index::subject_id::equals(subject.subject_id as i32),
+                    level,
+                    next_review,
+                    db::progress::id::equals(user.progress_id.clone()),
+                    params.clone(),
+                ),
+                params,
+            )
+            .exec()
+            .await?;
+        replayed += 1;
+    }
+    Ok(replayed)
+}
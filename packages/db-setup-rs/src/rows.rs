@@ -0,0 +1,601 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use japtools_core::{
+    furigana::ReadingComposition,
+    normalize::{meaning_keys, reading_keys, vocabulary_reading_keys},
+    pitch::{pitch_pattern, PitchPattern},
+    schema::{
+        AuxiliaryMeaning, CharacterImage, CharacterImageMetadata, ExampleSentence, ReadingType,
+        SubjectData, SubjectDataOuter, SubjectMeaning,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    IntArray(Vec<i64>),
+    TextArray(Vec<String>),
+    Enum(&'static str),
+    // Postgres enum type name and the values
+    EnumArray(&'static str, Vec<&'static str>),
+    Json(Value),
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<u32> for SqlValue {
+    fn from(value: u32) -> Self {
+        SqlValue::Int(value as i64)
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.to_string())
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(SqlValue::Null, Into::into)
+    }
+}
+
+impl From<&Vec<u32>> for SqlValue {
+    fn from(value: &Vec<u32>) -> Self {
+        SqlValue::IntArray(value.iter().map(|x| *x as i64).collect())
+    }
+}
+
+impl From<Value> for SqlValue {
+    fn from(value: Value) -> Self {
+        SqlValue::Json(value)
+    }
+}
+
+impl From<Vec<String>> for SqlValue {
+    fn from(value: Vec<String>) -> Self {
+        SqlValue::TextArray(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: &'static str,
+    pub columns: &'static [&'static str],
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl Table {
+    fn new(name: &'static str, columns: &'static [&'static str]) -> Self {
+        Table {
+            name,
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn column(&self, name: &str) -> usize {
+        self.columns
+            .iter()
+            .position(|x| *x == name)
+            .unwrap_or_else(|| panic!("{} has no column {}", self.name, name))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubjectTables {
+    pub subject_index: Table,
+    pub radical_subject: Table,
+    pub kanji_subject: Table,
+    pub vocabulary_subject: Table,
+    pub subject_meaning: Table,
+    pub auxiliary_meaning: Table,
+    pub kanji_reading: Table,
+    pub vocabulary_reading: Table,
+    pub context_sentence: Table,
+    pub example_sentence: Table,
+    pub subject_media: Table,
+}
+
+impl SubjectTables {
+    // Parent tables come before the tables referencing them
+    pub fn into_tables(self) -> Vec<Table> {
+        vec![
+            self.subject_index,
+            self.radical_subject,
+            self.kanji_subject,
+            self.vocabulary_subject,
+            self.subject_meaning,
+            self.auxiliary_meaning,
+            self.kanji_reading,
+            self.vocabulary_reading,
+            self.context_sentence,
+            self.example_sentence,
+            self.subject_media,
+        ]
+    }
+}
+
+// The SubjectType enum has no kana vocabulary yet, so it is left out of every table
+fn subject_type_name(data: &SubjectData) -> Option<&'static str> {
+    match data {
+        SubjectData::Radical(_) => Some("RADICAL"),
+        SubjectData::Kanji(_) => Some("KANJI"),
+        SubjectData::Vocabulary(_) => Some("VOCABULARY"),
+        SubjectData::KanaVocabulary(_) => None,
+    }
+}
+
+fn composition_name(composition: &ReadingComposition) -> &'static str {
+    match composition {
+        ReadingComposition::Regular => "REGULAR",
+        ReadingComposition::SoundChange => "SOUND_CHANGE",
+        ReadingComposition::Jukujikun => "JUKUJIKUN",
+    }
+}
+
+fn pitch_pattern_name(pattern: PitchPattern) -> &'static str {
+    match pattern {
+        PitchPattern::Heiban => "HEIBAN",
+        PitchPattern::Atamadaka => "ATAMADAKA",
+        PitchPattern::Nakadaka => "NAKADAKA",
+        PitchPattern::Odaka => "ODAKA",
+    }
+}
+
+fn reading_type_name(reading_type: &ReadingType) -> &'static str {
+    match reading_type {
+        ReadingType::Onyomi => "ONYOMI",
+        ReadingType::Kunyomi => "KUNYOMI",
+        ReadingType::Nanori => "NANORI",
+    }
+}
+
+// Kanji without KanjiVG data have an unknown stroke count rather than zero
+pub fn stroke_count(stroke_paths: &[String]) -> Option<u32> {
+    (!stroke_paths.is_empty()).then_some(stroke_paths.len() as u32)
+}
+
+// Empty lists are stored as NULL, the column is only filled once the data was computed
+pub fn json_column<T: Serialize>(values: &[T]) -> Option<Value> {
+    (!values.is_empty()).then(|| serde_json::to_value(values).expect("column is valid json"))
+}
+
+pub fn largest_png_url(images: &[CharacterImage]) -> Option<String> {
+    images
+        .iter()
+        .filter_map(|image| match &image.metadata {
+            CharacterImageMetadata::Png(data) => {
+                let (width, height) = data.dimensions.split_once('x')?;
+                let area = width.parse::<u32>().ok()? * height.parse::<u32>().ok()?;
+                Some((area, image.url.clone()))
+            }
+            _ => None,
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, url)| url)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    CharacterImage,
+    PronunciationAudio,
+}
+
+impl MediaKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MediaKind::CharacterImage => "CHARACTER_IMAGE",
+            MediaKind::PronunciationAudio => "PRONUNCIATION_AUDIO",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Media {
+    pub kind: MediaKind,
+    pub url: String,
+    pub content_type: String,
+    pub metadata: Option<Value>,
+}
+
+// Images and audio keep the content type and metadata WaniKani sends with them
+fn media<T: Serialize>(kind: MediaKind, item: &T) -> Option<Media> {
+    let value = serde_json::to_value(item).ok()?;
+    Some(Media {
+        kind,
+        url: value.get("url")?.as_str()?.to_string(),
+        content_type: value.get("content_type")?.as_str()?.to_string(),
+        metadata: value.get("metadata").cloned(),
+    })
+}
+
+pub fn subject_media(data: &SubjectData) -> Vec<Media> {
+    match data {
+        SubjectData::Radical(data) => data
+            .character_images
+            .iter()
+            .filter_map(|x| media(MediaKind::CharacterImage, x))
+            .collect(),
+        SubjectData::Vocabulary(data) => data
+            .pronunciation_audios
+            .iter()
+            .filter_map(|x| media(MediaKind::PronunciationAudio, x))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// Columns holding the owning subject for each subject kind, in Prisma order
+fn owner_columns(data: &SubjectData, subject_id: u32) -> [SqlValue; 3] {
+    let id = SqlValue::from(subject_id);
+    match data {
+        SubjectData::Radical(_) => [id, SqlValue::Null, SqlValue::Null],
+        SubjectData::Kanji(_) => [SqlValue::Null, id, SqlValue::Null],
+        _ => [SqlValue::Null, SqlValue::Null, id],
+    }
+}
+
+fn push_examples(
+    tables: &mut SubjectTables,
+    subject: &SubjectDataOuter,
+    examples: &[ExampleSentence],
+) {
+    for example in examples {
+        let mut row = vec![
+            format!("{}-example-{}", subject.id, example.rank).into(),
+            example.tatoeba_id.into(),
+            example.en.as_str().into(),
+            example.ja.as_str().into(),
+            example.rank.into(),
+            json_column(&example.tokens).into(),
+        ];
+        row.extend(owner_columns(&subject.data, subject.id).into_iter().skip(1));
+        tables.example_sentence.rows.push(row);
+    }
+}
+
+fn push_meanings(
+    tables: &mut SubjectTables,
+    subject: &SubjectDataOuter,
+    meanings: &[SubjectMeaning],
+    auxiliary_meanings: &[AuxiliaryMeaning],
+) {
+    for (i, meaning) in meanings.iter().enumerate() {
+        let mut row = vec![
+            format!("{}-meaning-{}", subject.id, i).into(),
+            meaning.accepted_answer.into(),
+            meaning.meaning.as_str().into(),
+            meaning.primary.into(),
+        ];
+        row.extend(owner_columns(&subject.data, subject.id));
+        tables.subject_meaning.rows.push(row);
+    }
+    for (i, auxiliary_meaning) in auxiliary_meanings.iter().enumerate() {
+        let mut row = vec![
+            format!("{}-auxiliary-{}", subject.id, i).into(),
+            auxiliary_meaning.meaning.as_str().into(),
+            auxiliary_meaning.meaning_type.as_str().into(),
+        ];
+        row.extend(owner_columns(&subject.data, subject.id));
+        tables.auxiliary_meaning.rows.push(row);
+    }
+}
+
+pub fn subject_tables(map: &HashMap<u32, SubjectDataOuter>) -> SubjectTables {
+    let mut tables = SubjectTables {
+        subject_index: Table::new(
+            "SubjectIndex",
+            &[
+                "subject_type",
+                "subjectId",
+                "readings",
+                "meanings",
+                "reading_keys",
+                "meaning_keys",
+                "level",
+            ],
+        ),
+        radical_subject: Table::new(
+            "RadicalSubject",
+            &[
+                "id",
+                "amalgamation_subject_ids",
+                "characters",
+                "lesson_position",
+                "level",
+                "meaning_mnemonic",
+                "image_url",
+            ],
+        ),
+        kanji_subject: Table::new(
+            "KanjiSubject",
+            &[
+                "id",
+                "amalgamation_subject_ids",
+                "characters",
+                "component_subject_ids",
+                "lesson_position",
+                "level",
+                "meaning_hint",
+                "meaning_mnemonic",
+                "reading_hint",
+                "reading_mnemonic",
+                "visually_similar_subject_ids",
+                "stroke_count",
+                "stroke_paths",
+            ],
+        ),
+        vocabulary_subject: Table::new(
+            "VocabularySubject",
+            &[
+                "id",
+                "characters",
+                "component_subject_ids",
+                "lesson_position",
+                "level",
+                "meaning_mnemonic",
+                "reading_mnemonic",
+            ],
+        ),
+        subject_meaning: Table::new(
+            "SubjectMeaning",
+            &[
+                "id",
+                "accepted_answer",
+                "meaning",
+                "primary",
+                "radicalSubjectId",
+                "kanjiSubjectId",
+                "vocabularySubjectId",
+            ],
+        ),
+        auxiliary_meaning: Table::new(
+            "AuxiliaryMeaning",
+            &[
+                "id",
+                "meaning",
+                "auxiliary_type",
+                "radicalSubjectId",
+                "kanjiSubjectId",
+                "vocabularySubjectId",
+            ],
+        ),
+        kanji_reading: Table::new(
+            "KanjiReading",
+            &["id", "reading", "reading_type", "primary", "kanjiSubjectId"],
+        ),
+        vocabulary_reading: Table::new(
+            "VocabularyReading",
+            &[
+                "id",
+                "reading",
+                "primary",
+                "accepted_answer",
+                "vocabularySubjectId",
+                "pitch_accents",
+                "pitch_patterns",
+                "furigana",
+                "composition",
+            ],
+        ),
+        context_sentence: Table::new(
+            "ContextSentence",
+            &["id", "en", "ja", "vocabularySubjectId", "tokens"],
+        ),
+        example_sentence: Table::new(
+            "ExampleSentence",
+            &[
+                "id",
+                "tatoeba_id",
+                "en",
+                "ja",
+                "rank",
+                "tokens",
+                "kanjiSubjectId",
+                "vocabularySubjectId",
+            ],
+        ),
+        subject_media: Table::new(
+            "SubjectMedia",
+            &[
+                "id",
+                "subject_id",
+                "kind",
+                "url",
+                "content_type",
+                "metadata",
+            ],
+        ),
+    };
+
+    let mut skipped = 0;
+    for subject in map.values().sorted_by_key(|subject| subject.id) {
+        let subject_type = match subject_type_name(&subject.data) {
+            Some(subject_type) => subject_type,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let (readings, meanings, level) = match &subject.data {
+            SubjectData::Radical(data) => (vec![], &data.meanings, data.level),
+            SubjectData::Kanji(data) => (
+                data.readings
+                    .iter()
+                    .map(|x| x.reading.as_str())
+                    .collect_vec(),
+                &data.meanings,
+                data.level,
+            ),
+            SubjectData::Vocabulary(data) => (
+                data.readings
+                    .iter()
+                    .map(|x| x.reading.as_str())
+                    .collect_vec(),
+                &data.meanings,
+                data.level,
+            ),
+            SubjectData::KanaVocabulary(_) => continue,
+        };
+        let meanings = meanings.iter().map(|x| x.meaning.as_str()).collect_vec();
+        let index_meanings = match subject.data {
+            SubjectData::Radical(_) => vec![],
+            _ => meanings.iter().map(|x| x.to_string()).collect_vec(),
+        };
+        let index_reading_keys = match &subject.data {
+            SubjectData::Vocabulary(data) => vocabulary_reading_keys(&data.readings),
+            _ => reading_keys(readings.iter().copied()),
+        };
+        tables.subject_index.rows.push(vec![
+            SqlValue::Enum(subject_type),
+            subject.id.into(),
+            readings.iter().map(|x| x.to_string()).collect_vec().into(),
+            index_meanings.into(),
+            index_reading_keys.into(),
+            meaning_keys(meanings.iter().copied()).into(),
+            level.into(),
+        ]);
+
+        match &subject.data {
+            SubjectData::Radical(data) => {
+                tables.radical_subject.rows.push(vec![
+                    subject.id.into(),
+                    (&data.amalgamation_subject_ids).into(),
+                    data.characters.clone().into(),
+                    data.lesson_position.into(),
+                    data.level.into(),
+                    data.meaning_mnemonic.as_str().into(),
+                    largest_png_url(&data.character_images).into(),
+                ]);
+                push_meanings(
+                    &mut tables,
+                    subject,
+                    &data.meanings,
+                    &data.auxiliary_meanings,
+                );
+            }
+            SubjectData::Kanji(data) => {
+                tables.kanji_subject.rows.push(vec![
+                    subject.id.into(),
+                    (&data.amalgamation_subject_ids).into(),
+                    data.characters.as_str().into(),
+                    (&data.component_subject_ids).into(),
+                    data.lesson_position.into(),
+                    data.level.into(),
+                    data.meaning_hint.clone().into(),
+                    data.meaning_mnemonic.as_str().into(),
+                    data.reading_hint.as_str().into(),
+                    data.reading_mnemonic.as_str().into(),
+                    (&data.visually_similar_subject_ids).into(),
+                    stroke_count(&data.stroke_paths).into(),
+                    data.stroke_paths.clone().into(),
+                ]);
+                push_meanings(
+                    &mut tables,
+                    subject,
+                    &data.meanings,
+                    &data.auxiliary_meanings,
+                );
+                for (i, reading) in data.readings.iter().enumerate() {
+                    tables.kanji_reading.rows.push(vec![
+                        format!("{}-reading-{}", subject.id, i).into(),
+                        reading.reading.as_str().into(),
+                        SqlValue::Enum(reading_type_name(&reading.reading_type)),
+                        reading.primary.into(),
+                        subject.id.into(),
+                    ]);
+                }
+                push_examples(&mut tables, subject, &data.example_sentences);
+            }
+            SubjectData::Vocabulary(data) => {
+                tables.vocabulary_subject.rows.push(vec![
+                    subject.id.into(),
+                    data.characters.as_str().into(),
+                    (&data.component_subject_ids).into(),
+                    data.lesson_position.into(),
+                    data.level.into(),
+                    data.meaning_mnemonic.as_str().into(),
+                    data.reading_mnemonic.as_str().into(),
+                ]);
+                push_meanings(
+                    &mut tables,
+                    subject,
+                    &data.meanings,
+                    &data.auxiliary_meanings,
+                );
+                for (i, reading) in data.readings.iter().enumerate() {
+                    tables.vocabulary_reading.rows.push(vec![
+                        format!("{}-reading-{}", subject.id, i).into(),
+                        reading.reading.as_str().into(),
+                        reading.primary.into(),
+                        reading.accepted_answer.into(),
+                        subject.id.into(),
+                        (&reading.pitch_accents).into(),
+                        SqlValue::EnumArray(
+                            "PitchPattern",
+                            reading
+                                .pitch_accents
+                                .iter()
+                                .map(|x| pitch_pattern_name(pitch_pattern(&reading.reading, *x)))
+                                .collect(),
+                        ),
+                        json_column(&reading.furigana).into(),
+                        reading
+                            .composition
+                            .as_ref()
+                            .map_or(SqlValue::Null, |x| SqlValue::Enum(composition_name(x))),
+                    ]);
+                }
+                for (i, sentence) in data.context_sentences.iter().enumerate() {
+                    tables.context_sentence.rows.push(vec![
+                        format!("{}-sentence-{}", subject.id, i).into(),
+                        sentence.en.as_str().into(),
+                        sentence.ja.as_str().into(),
+                        subject.id.into(),
+                        json_column(&sentence.tokens).into(),
+                    ]);
+                }
+                push_examples(&mut tables, subject, &data.example_sentences);
+            }
+            SubjectData::KanaVocabulary(_) => {}
+        }
+        for (i, media) in subject_media(&subject.data).into_iter().enumerate() {
+            tables.subject_media.rows.push(vec![
+                format!("{}-media-{}", subject.id, i).into(),
+                subject.id.into(),
+                SqlValue::Enum(media.kind.name()),
+                media.url.into(),
+                media.content_type.into(),
+                media.metadata.into(),
+            ]);
+        }
+    }
+    if skipped > 0 {
+        warn!(
+            skipped,
+            "kana vocabulary has no table yet and is not exported"
+        );
+    }
+
+    tables
+}
@@ -0,0 +1,137 @@
+use std::{collections::HashMap, error::Error, fmt::Write, path::Path};
+
+use itertools::Itertools;
+
+use japtools_core::schema::SubjectDataOuter;
+
+use crate::rows::{subject_tables, SqlValue, Table};
+
+const BATCH_SIZE: usize = 500;
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn render_value(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Null => "NULL".to_string(),
+        SqlValue::Bool(value) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+        SqlValue::Int(value) => value.to_string(),
+        SqlValue::Text(value) => quote(value),
+        SqlValue::Enum(value) => quote(value),
+        SqlValue::IntArray(values) => {
+            format!("ARRAY[{}]::INT4[]", values.iter().join(","))
+        }
+        SqlValue::TextArray(values) => format!(
+            "ARRAY[{}]::TEXT[]",
+            values.iter().map(|x| quote(x)).join(",")
+        ),
+        SqlValue::EnumArray(name, values) => format!(
+            "ARRAY[{}]::\"{}\"[]",
+            values.iter().map(|x| quote(x)).join(","),
+            name
+        ),
+        SqlValue::Json(value) => format!("{}::JSONB", quote(&value.to_string())),
+    }
+}
+
+fn render_table(out: &mut String, table: &Table) -> std::fmt::Result {
+    if table.rows.is_empty() {
+        return Ok(());
+    }
+    let columns = table
+        .columns
+        .iter()
+        .map(|x| format!(r#""{}""#, x))
+        .join(", ");
+    writeln!(out, "-- {} ({} rows)", table.name, table.rows.len())?;
+    for batch in table.rows.chunks(BATCH_SIZE) {
+        writeln!(out, r#"INSERT INTO "{}" ({}) VALUES"#, table.name, columns)?;
+        let values = batch
+            .iter()
+            .map(|row| format!("({})", row.iter().map(render_value).join(", ")))
+            .join(",\n");
+        writeln!(out, "{};", values)?;
+    }
+    writeln!(out)
+}
+
+pub fn render_seed(map: &HashMap<u32, SubjectDataOuter>) -> Result<String, Box<dyn Error>> {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "-- Generated by dbsetup, apply to a freshly migrated database"
+    )?;
+    writeln!(out, "BEGIN;")?;
+    writeln!(out)?;
+    for table in subject_tables(map).into_tables() {
+        render_table(&mut out, &table)?;
+    }
+    writeln!(out, "COMMIT;")?;
+    Ok(out)
+}
+
+pub fn write_seed(map: &HashMap<u32, SubjectDataOuter>, path: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, render_seed(map)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &[&str] = &[
+        include_str!("../../japtools-core/tests/fixtures/subjects/radical.json"),
+        include_str!("../../japtools-core/tests/fixtures/subjects/kanji.json"),
+        include_str!("../../japtools-core/tests/fixtures/subjects/vocabulary.json"),
+        include_str!("../../japtools-core/tests/fixtures/subjects/vocabulary_readings.json"),
+    ];
+
+    fn map<'a>(fixtures: impl Iterator<Item = &'a &'a str>) -> HashMap<u32, SubjectDataOuter> {
+        let mut map = HashMap::new();
+        for json in fixtures {
+            let subject: SubjectDataOuter = serde_json::from_str(json).unwrap();
+            map.insert(subject.id, subject);
+        }
+        map
+    }
+
+    #[test]
+    fn renders_the_same_seed_every_time() {
+        let forward = map(FIXTURES.iter());
+        let reversed = map(FIXTURES.iter().rev());
+        let seed = render_seed(&forward).unwrap();
+        assert_eq!(render_seed(&forward).unwrap(), seed);
+        assert_eq!(render_seed(&reversed).unwrap(), seed);
+        assert!(seed.starts_with("-- Generated by dbsetup"));
+        assert!(seed.contains("-- SubjectIndex (4 rows)\n"));
+        assert!(seed.ends_with("COMMIT;\n"));
+    }
+
+    #[test]
+    fn splits_large_tables_into_batches() {
+        let table = Table {
+            name: "SubjectIndex",
+            columns: &["subjectId", "meanings"],
+            rows: (0..1001)
+                .map(|i| {
+                    vec![
+                        SqlValue::Int(i),
+                        SqlValue::TextArray(vec!["it's".to_string()]),
+                    ]
+                })
+                .collect(),
+        };
+        let mut out = String::new();
+        render_table(&mut out, &table).unwrap();
+        let batches = out
+            .split("INSERT INTO")
+            .skip(1)
+            .map(|x| x.lines().filter(|x| x.starts_with('(')).count())
+            .collect_vec();
+        assert_eq!(batches, [BATCH_SIZE, BATCH_SIZE, 1]);
+        assert!(out.starts_with("-- SubjectIndex (1001 rows)\n"));
+        assert!(out.contains(r#"INSERT INTO "SubjectIndex" ("subjectId", "meanings") VALUES"#));
+        assert!(out.contains("(1000, ARRAY['it''s']::TEXT[]);"));
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use japtools_core::{
+    schema::{SubjectData, SubjectDataOuter},
+    tokenize::Dictionary,
+};
+use tracing::info;
+
+pub fn subject_dictionary(map: &HashMap<u32, SubjectDataOuter>) -> Dictionary {
+    let mut dictionary = Dictionary::default();
+    for subject in map.values() {
+        match &subject.data {
+            SubjectData::Kanji(data) => dictionary.add_kanji(&data.characters, subject.id),
+            SubjectData::Vocabulary(data) => {
+                dictionary.add_vocabulary(&data.characters, subject.id)
+            }
+            SubjectData::KanaVocabulary(data) => {
+                if let Some(characters) = data.get("characters").and_then(|x| x.as_str()) {
+                    dictionary.add_vocabulary(characters, subject.id);
+                }
+            }
+            SubjectData::Radical(_) => {}
+        }
+    }
+    dictionary
+}
+
+// Only subjects in the loaded levels are linked, words from other levels stay plain text
+pub fn tokenize_sentences(map: &mut HashMap<u32, SubjectDataOuter>) {
+    let dictionary = subject_dictionary(map);
+    let mut sentences = 0;
+    let mut linked = 0;
+    for subject in map.values_mut() {
+        if let SubjectData::Vocabulary(data) = &mut subject.data {
+            for sentence in &mut data.context_sentences {
+                sentence.tokens = dictionary.tokenize(&sentence.ja);
+                sentences += 1;
+                linked += sentence
+                    .tokens
+                    .iter()
+                    .filter(|x| x.subject_id.is_some())
+                    .count();
+            }
+        }
+    }
+    info!(sentences, linked, "tokenized context sentences");
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    ops::RangeInclusive,
+    path::PathBuf,
+};
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use japtools_core::schema::{SubjectData, SubjectDataOuter};
+use tracing::{info, info_span, Instrument};
+
+use crate::{
+    jmdict::read_jmdict,
+    kanjidic::read_kanjidic,
+    loader::{load_wanikani_data, MAX_LEVEL},
+};
+
+// Imported subjects get ids far above WaniKani's so several sources can share one database
+pub const KANJIDIC_ID_OFFSET: u32 = 10_000_000;
+pub const JMDICT_ID_OFFSET: u32 = 20_000_000;
+
+pub const KANJIDIC_FILE: &str = "target/kanjidic2.xml";
+pub const JMDICT_FILE: &str = "target/JMdict_e.xml";
+
+#[async_trait(?Send)]
+pub trait SubjectSource {
+    fn name(&self) -> &'static str;
+
+    async fn load(
+        &self,
+        levels: RangeInclusive<u32>,
+    ) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>>;
+}
+
+pub struct WaniKaniSource {
+    pub cache: PathBuf,
+}
+
+#[async_trait(?Send)]
+impl SubjectSource for WaniKaniSource {
+    fn name(&self) -> &'static str {
+        "wanikani"
+    }
+
+    async fn load(
+        &self,
+        levels: RangeInclusive<u32>,
+    ) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+        load_wanikani_data(&self.cache, levels).await
+    }
+}
+
+pub struct KanjidicSource {
+    pub path: PathBuf,
+}
+
+#[async_trait(?Send)]
+impl SubjectSource for KanjidicSource {
+    fn name(&self) -> &'static str {
+        "kanjidic"
+    }
+
+    async fn load(
+        &self,
+        levels: RangeInclusive<u32>,
+    ) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+        let xml = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| format!("could not read {}: {}", self.path.display(), e))?;
+        read_kanjidic(&xml, &levels)
+    }
+}
+
+pub struct JmdictSource {
+    pub path: PathBuf,
+}
+
+#[async_trait(?Send)]
+impl SubjectSource for JmdictSource {
+    fn name(&self) -> &'static str {
+        "jmdict"
+    }
+
+    async fn load(
+        &self,
+        levels: RangeInclusive<u32>,
+    ) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+        let xml = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| format!("could not read {}: {}", self.path.display(), e))?;
+        read_jmdict(&xml, &levels)
+    }
+}
+
+pub fn is_imported(id: u32) -> bool {
+    id >= KANJIDIC_ID_OFFSET
+}
+
+// Kanji and vocabulary are told apart by their characters, imported ones have no other id
+fn characters_key(data: &SubjectData) -> Option<(&'static str, &str)> {
+    match data {
+        SubjectData::Kanji(data) => Some(("kanji", &data.characters)),
+        SubjectData::Vocabulary(data) => Some(("vocabulary", &data.characters)),
+        _ => None,
+    }
+}
+
+// KanjiSubject.characters is unique and a word should only be learned once, so WaniKani
+// subjects win over imported ones with the same characters
+fn drop_duplicate_subjects(map: &mut HashMap<u32, SubjectDataOuter>) {
+    let wanikani = map
+        .values()
+        .filter(|x| !is_imported(x.id))
+        .filter_map(|x| characters_key(&x.data))
+        .map(|(kind, characters)| (kind, characters.to_string()))
+        .collect::<HashSet<_>>();
+    let mut dropped: HashMap<&'static str, usize> = HashMap::new();
+    map.retain(|id, subject| match characters_key(&subject.data) {
+        Some((kind, characters))
+            if is_imported(*id) && wanikani.contains(&(kind, characters.to_string())) =>
+        {
+            *dropped.entry(kind).or_default() += 1;
+            false
+        }
+        _ => true,
+    });
+    for (kind, dropped) in dropped.into_iter().sorted() {
+        info!(
+            kind,
+            dropped, "imported subjects already provided by WaniKani"
+        );
+    }
+}
+
+// Dictionary kanji and vocabulary only know each other through their characters
+fn link_imported(map: &mut HashMap<u32, SubjectDataOuter>) {
+    let kanji_ids = map
+        .values()
+        .filter_map(|x| match &x.data {
+            SubjectData::Kanji(data) => Some((data.characters.clone(), x.id)),
+            _ => None,
+        })
+        .collect::<HashMap<String, u32>>();
+
+    let mut amalgamations: HashMap<u32, HashSet<u32>> = HashMap::new();
+    for subject in map.values_mut().filter(|x| is_imported(x.id)) {
+        if let SubjectData::Vocabulary(data) = &mut subject.data {
+            data.component_subject_ids = data
+                .characters
+                .chars()
+                .filter_map(|c| kanji_ids.get(&c.to_string()).copied())
+                .unique()
+                .collect();
+            for id in &data.component_subject_ids {
+                amalgamations.entry(*id).or_default().insert(subject.id);
+            }
+        }
+    }
+    // WaniKani kanji keep their own amalgamations
+    for (kanji_id, vocabulary_ids) in amalgamations {
+        if !is_imported(kanji_id) {
+            continue;
+        }
+        if let Some(SubjectData::Kanji(data)) = map.get_mut(&kanji_id).map(|x| &mut x.data) {
+            data.amalgamation_subject_ids = vocabulary_ids.into_iter().sorted().collect();
+        }
+    }
+}
+
+pub async fn load_sources(
+    sources: &[Box<dyn SubjectSource>],
+    levels: RangeInclusive<u32>,
+) -> Result<HashMap<u32, SubjectDataOuter>, Box<dyn Error>> {
+    let mut map = HashMap::new();
+    for source in sources {
+        let subjects = source
+            .load(levels.clone())
+            .instrument(info_span!("load_source", source = source.name()))
+            .await?;
+        info!(
+            source = source.name(),
+            subjects = subjects.len(),
+            "loaded subjects"
+        );
+        for (id, subject) in subjects {
+            if map.insert(id, subject).is_some() {
+                return Err(format!("subject {} is provided by more than one source", id).into());
+            }
+        }
+    }
+    drop_duplicate_subjects(&mut map);
+    link_imported(&mut map);
+    Ok(map)
+}
+
+// Spreads a frequency rank (1 = most common) evenly over the levels
+pub fn level_from_rank(rank: u32, ranks: u32) -> u32 {
+    1 + (rank.clamp(1, ranks) - 1) * MAX_LEVEL / ranks
+}
+
+// Dictionaries only carry a creation date, WaniKani timestamps look like 2012-02-27T18:08:16.000000Z
+pub fn dictionary_timestamp(date: &str) -> String {
+    format!("{}T00:00:00.000000Z", date.trim())
+}
+
+// Positions count up within each level in the order subjects are given
+pub fn lesson_positions(levels: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut next: HashMap<u32, u32> = HashMap::new();
+    levels
+        .into_iter()
+        .map(|level| {
+            let position = next.entry(level).or_default();
+            *position += 1;
+            *position - 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_imported_subjects_provided_by_wanikani() {
+        let xml = include_str!("../tests/fixtures/jmdict.xml");
+        let mut map = read_jmdict(xml, &(1..=60)).unwrap();
+        let mut student = map[&(JMDICT_ID_OFFSET + 1311110)].clone();
+        student.id = 2467;
+        map.insert(student.id, student);
+
+        drop_duplicate_subjects(&mut map);
+        assert!(map.contains_key(&2467));
+        assert!(!map.contains_key(&(JMDICT_ID_OFFSET + 1311110)));
+        assert!(map.contains_key(&(JMDICT_ID_OFFSET + 1358280)));
+    }
+}
@@ -0,0 +1,353 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use itertools::Itertools;
+use rusqlite::{
+    params, params_from_iter,
+    types::{ToSqlOutput, Value},
+    Connection, ToSql,
+};
+
+use japtools_core::schema::SubjectDataOuter;
+
+use crate::rows::{subject_tables, SqlValue};
+
+pub const SQLITE_SCHEMA_VERSION: i64 = 9;
+
+// Mirrors the subject models of prisma/schema.prisma, arrays are stored as JSON text
+const SCHEMA: &str = r#"
+CREATE TABLE "SchemaVersion" (
+    "version" INTEGER NOT NULL PRIMARY KEY,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE "SubjectIndex" (
+    "subject_type" TEXT NOT NULL CHECK ("subject_type" IN ('KANJI', 'VOCABULARY', 'RADICAL')),
+    "subjectId" INTEGER NOT NULL PRIMARY KEY,
+    "readings" TEXT NOT NULL,
+    "meanings" TEXT NOT NULL,
+    "reading_keys" TEXT NOT NULL,
+    "meaning_keys" TEXT NOT NULL,
+    "level" INTEGER NOT NULL
+);
+
+CREATE TABLE "SubjectSearchKey" (
+    "subjectId" INTEGER NOT NULL REFERENCES "SubjectIndex"("subjectId") ON DELETE CASCADE,
+    "kind" TEXT NOT NULL CHECK ("kind" IN ('READING', 'MEANING')),
+    "key" TEXT NOT NULL,
+    PRIMARY KEY ("kind", "key", "subjectId")
+) WITHOUT ROWID;
+
+CREATE TABLE "RadicalSubject" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "amalgamation_subject_ids" TEXT NOT NULL,
+    "characters" TEXT,
+    "lesson_position" INTEGER NOT NULL,
+    "level" INTEGER NOT NULL,
+    "meaning_mnemonic" TEXT NOT NULL,
+    "image_url" TEXT
+);
+
+CREATE TABLE "KanjiSubject" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "amalgamation_subject_ids" TEXT NOT NULL,
+    "characters" TEXT NOT NULL UNIQUE,
+    "component_subject_ids" TEXT NOT NULL,
+    "lesson_position" INTEGER NOT NULL,
+    "level" INTEGER NOT NULL,
+    "meaning_hint" TEXT,
+    "meaning_mnemonic" TEXT NOT NULL,
+    "reading_hint" TEXT NOT NULL,
+    "reading_mnemonic" TEXT NOT NULL,
+    "visually_similar_subject_ids" TEXT NOT NULL,
+    "stroke_count" INTEGER,
+    "stroke_paths" TEXT NOT NULL
+);
+
+CREATE TABLE "VocabularySubject" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "characters" TEXT NOT NULL,
+    "component_subject_ids" TEXT NOT NULL,
+    "lesson_position" INTEGER NOT NULL,
+    "level" INTEGER NOT NULL,
+    "meaning_mnemonic" TEXT NOT NULL,
+    "reading_mnemonic" TEXT NOT NULL
+);
+
+CREATE TABLE "SubjectMeaning" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "accepted_answer" INTEGER NOT NULL,
+    "meaning" TEXT NOT NULL,
+    "primary" INTEGER NOT NULL,
+    "radicalSubjectId" INTEGER REFERENCES "RadicalSubject"("id") ON DELETE CASCADE,
+    "kanjiSubjectId" INTEGER REFERENCES "KanjiSubject"("id") ON DELETE CASCADE,
+    "vocabularySubjectId" INTEGER REFERENCES "VocabularySubject"("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "AuxiliaryMeaning" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "meaning" TEXT NOT NULL,
+    "auxiliary_type" TEXT NOT NULL,
+    "radicalSubjectId" INTEGER REFERENCES "RadicalSubject"("id") ON DELETE CASCADE,
+    "kanjiSubjectId" INTEGER REFERENCES "KanjiSubject"("id") ON DELETE CASCADE,
+    "vocabularySubjectId" INTEGER REFERENCES "VocabularySubject"("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "KanjiReading" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "reading" TEXT NOT NULL,
+    "reading_type" TEXT NOT NULL CHECK ("reading_type" IN ('ONYOMI', 'KUNYOMI', 'NANORI')),
+    "primary" INTEGER NOT NULL,
+    "kanjiSubjectId" INTEGER REFERENCES "KanjiSubject"("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "VocabularyReading" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "reading" TEXT NOT NULL,
+    "primary" INTEGER NOT NULL,
+    "accepted_answer" INTEGER NOT NULL,
+    "vocabularySubjectId" INTEGER REFERENCES "VocabularySubject"("id") ON DELETE CASCADE,
+    "pitch_accents" TEXT NOT NULL,
+    "pitch_patterns" TEXT NOT NULL,
+    "furigana" TEXT,
+    "composition" TEXT CHECK ("composition" IN ('REGULAR', 'SOUND_CHANGE', 'JUKUJIKUN'))
+);
+
+CREATE TABLE "ContextSentence" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "en" TEXT NOT NULL,
+    "ja" TEXT NOT NULL,
+    "vocabularySubjectId" INTEGER REFERENCES "VocabularySubject"("id") ON DELETE CASCADE,
+    "tokens" TEXT
+);
+
+CREATE TABLE "ExampleSentence" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "tatoeba_id" INTEGER NOT NULL,
+    "en" TEXT NOT NULL,
+    "ja" TEXT NOT NULL,
+    "rank" INTEGER NOT NULL,
+    "tokens" TEXT,
+    "kanjiSubjectId" INTEGER REFERENCES "KanjiSubject"("id") ON DELETE CASCADE,
+    "vocabularySubjectId" INTEGER REFERENCES "VocabularySubject"("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "SubjectMedia" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "subject_id" INTEGER NOT NULL REFERENCES "SubjectIndex"("subjectId") ON DELETE CASCADE,
+    "kind" TEXT NOT NULL CHECK ("kind" IN ('CHARACTER_IMAGE', 'PRONUNCIATION_AUDIO')),
+    "url" TEXT NOT NULL,
+    "content_type" TEXT NOT NULL,
+    "metadata" TEXT
+);
+
+CREATE INDEX "SubjectIndex_level_idx" ON "SubjectIndex"("level");
+CREATE INDEX "SubjectMeaning_radicalSubjectId_idx" ON "SubjectMeaning"("radicalSubjectId");
+CREATE INDEX "SubjectMeaning_kanjiSubjectId_idx" ON "SubjectMeaning"("kanjiSubjectId");
+CREATE INDEX "SubjectMeaning_vocabularySubjectId_idx" ON "SubjectMeaning"("vocabularySubjectId");
+CREATE INDEX "AuxiliaryMeaning_radicalSubjectId_idx" ON "AuxiliaryMeaning"("radicalSubjectId");
+CREATE INDEX "AuxiliaryMeaning_kanjiSubjectId_idx" ON "AuxiliaryMeaning"("kanjiSubjectId");
+CREATE INDEX "AuxiliaryMeaning_vocabularySubjectId_idx" ON "AuxiliaryMeaning"("vocabularySubjectId");
+CREATE INDEX "KanjiReading_kanjiSubjectId_idx" ON "KanjiReading"("kanjiSubjectId");
+CREATE INDEX "VocabularyReading_vocabularySubjectId_idx" ON "VocabularyReading"("vocabularySubjectId");
+CREATE INDEX "ContextSentence_vocabularySubjectId_idx" ON "ContextSentence"("vocabularySubjectId");
+CREATE INDEX "ExampleSentence_kanjiSubjectId_idx" ON "ExampleSentence"("kanjiSubjectId");
+CREATE INDEX "ExampleSentence_vocabularySubjectId_idx" ON "ExampleSentence"("vocabularySubjectId");
+CREATE INDEX "SubjectMedia_subject_id_idx" ON "SubjectMedia"("subject_id");
+"#;
+
+impl ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            SqlValue::Null => ToSqlOutput::Owned(Value::Null),
+            SqlValue::Bool(value) => ToSqlOutput::Owned(Value::Integer(*value as i64)),
+            SqlValue::Int(value) => ToSqlOutput::Owned(Value::Integer(*value)),
+            SqlValue::Text(value) => ToSqlOutput::Borrowed(value.as_str().into()),
+            SqlValue::Enum(value) => ToSqlOutput::Borrowed((*value).into()),
+            SqlValue::IntArray(value) => ToSqlOutput::Owned(Value::Text(
+                serde_json::to_string(value).expect("int array is valid json"),
+            )),
+            SqlValue::TextArray(value) => ToSqlOutput::Owned(Value::Text(
+                serde_json::to_string(value).expect("string array is valid json"),
+            )),
+            SqlValue::EnumArray(_, value) => ToSqlOutput::Owned(Value::Text(
+                serde_json::to_string(value).expect("string array is valid json"),
+            )),
+            SqlValue::Json(value) => ToSqlOutput::Owned(Value::Text(value.to_string())),
+        })
+    }
+}
+
+fn write_tables(
+    connection: &mut Connection,
+    map: &HashMap<u32, SubjectDataOuter>,
+) -> Result<(), Box<dyn Error>> {
+    connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    transaction.execute(
+        r#"INSERT INTO "SchemaVersion" ("version") VALUES (?1)"#,
+        params![SQLITE_SCHEMA_VERSION],
+    )?;
+
+    let tables = subject_tables(map);
+    // SQLite has no inverted indexes, so the key arrays are also exploded into rows
+    let index = &tables.subject_index;
+    let (subject_id, reading_keys, meaning_keys) = (
+        index.column("subjectId"),
+        index.column("reading_keys"),
+        index.column("meaning_keys"),
+    );
+    let mut search_keys = Vec::new();
+    for row in &index.rows {
+        for (kind, i) in [("READING", reading_keys), ("MEANING", meaning_keys)] {
+            if let SqlValue::TextArray(keys) = &row[i] {
+                for key in keys {
+                    search_keys.push((row[subject_id].clone(), kind, key.clone()));
+                }
+            }
+        }
+    }
+
+    for table in tables.into_tables() {
+        let sql = format!(
+            r#"INSERT INTO "{}" ({}) VALUES ({})"#,
+            table.name,
+            table
+                .columns
+                .iter()
+                .map(|x| format!(r#""{}""#, x))
+                .join(", "),
+            (1..=table.columns.len())
+                .map(|i| format!("?{}", i))
+                .join(", "),
+        );
+        let mut statement = transaction.prepare(&sql)?;
+        for row in &table.rows {
+            statement.execute(params_from_iter(row.iter()))?;
+        }
+    }
+
+    {
+        let mut statement = transaction.prepare(
+            r#"INSERT INTO "SubjectSearchKey" ("subjectId", "kind", "key") VALUES (?1, ?2, ?3)"#,
+        )?;
+        for (subject_id, kind, key) in &search_keys {
+            statement.execute(params![subject_id, kind, key])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+pub fn write_sqlite(
+    map: &HashMap<u32, SubjectDataOuter>,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch("PRAGMA journal_mode = OFF;")?;
+    write_tables(&mut connection, map)?;
+    connection.execute_batch("VACUUM;")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(json: &str) -> (u32, SubjectDataOuter) {
+        let subject: SubjectDataOuter = serde_json::from_str(json).unwrap();
+        (subject.id, subject)
+    }
+
+    fn fixtures() -> HashMap<u32, SubjectDataOuter> {
+        HashMap::from([
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/radical.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/kanji.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/vocabulary.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/vocabulary_readings.json"
+            )),
+            subject(include_str!(
+                "../../japtools-core/tests/fixtures/subjects/kana_vocabulary.json"
+            )),
+        ])
+    }
+
+    #[test]
+    fn exports_subject_fixtures() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        write_tables(&mut connection, &fixtures()).unwrap();
+        let query = |sql: &str| -> Vec<String> {
+            let mut statement = connection.prepare(sql).unwrap();
+            let rows = statement
+                .query_map([], |row| row.get::<_, Value>(0))
+                .unwrap()
+                .map(|x| match x.unwrap() {
+                    Value::Integer(x) => x.to_string(),
+                    Value::Text(x) => x,
+                    x => format!("{:?}", x),
+                })
+                .collect();
+            rows
+        };
+
+        assert_eq!(
+            query(r#"SELECT "version" FROM "SchemaVersion""#),
+            [SQLITE_SCHEMA_VERSION.to_string()]
+        );
+        assert_eq!(
+            query(r#"SELECT "name" FROM "sqlite_master" WHERE "type" = 'table' ORDER BY "name""#),
+            [
+                "AuxiliaryMeaning",
+                "ContextSentence",
+                "ExampleSentence",
+                "KanjiReading",
+                "KanjiSubject",
+                "RadicalSubject",
+                "SchemaVersion",
+                "SubjectIndex",
+                "SubjectMeaning",
+                "SubjectMedia",
+                "SubjectSearchKey",
+                "VocabularyReading",
+                "VocabularySubject",
+            ]
+        );
+
+        // Kana vocabulary has no table and is left out
+        assert_eq!(
+            query(
+                r#"SELECT "subjectId" || ' ' || "subject_type" FROM "SubjectIndex" ORDER BY "subjectId""#
+            ),
+            [
+                "1 RADICAL",
+                "440 KANJI",
+                "2467 VOCABULARY",
+                "2560 VOCABULARY"
+            ]
+        );
+        assert_eq!(query(r#"SELECT "characters" FROM "KanjiSubject""#), ["一"]);
+        assert_eq!(
+            query(
+                r#"SELECT "reading" || ' ' || "pitch_accents" FROM "VocabularyReading"
+                   WHERE "vocabularySubjectId" = 2560 ORDER BY "id""#
+            ),
+            ["おとな []", "だいにん []"]
+        );
+        assert_eq!(
+            query(
+                r#"SELECT "kind" FROM "SubjectSearchKey" WHERE "subjectId" = 440 AND "key" = 'one'"#
+            ),
+            ["MEANING"]
+        );
+    }
+}
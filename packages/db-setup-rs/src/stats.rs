@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, HashMap};
+
+use japtools_core::{
+    furigana::ReadingComposition,
+    schema::{SubjectData, SubjectDataOuter},
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelStats {
+    pub radicals: usize,
+    pub kanji: usize,
+    pub vocabulary: usize,
+    pub kana_vocabulary: usize,
+}
+
+impl LevelStats {
+    fn add(&mut self, data: &SubjectData) {
+        match data {
+            SubjectData::Radical(_) => self.radicals += 1,
+            SubjectData::Kanji(_) => self.kanji += 1,
+            SubjectData::Vocabulary(_) => self.vocabulary += 1,
+            SubjectData::KanaVocabulary(_) => self.kana_vocabulary += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.radicals + self.kanji + self.vocabulary + self.kana_vocabulary
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SubjectStats {
+    pub total: LevelStats,
+    pub levels: BTreeMap<u32, LevelStats>,
+    pub readings: usize,
+    pub meanings: usize,
+    pub context_sentences: usize,
+    pub regular_readings: usize,
+    pub sound_change_readings: usize,
+    pub jukujikun_readings: usize,
+}
+
+pub fn subject_stats(map: &HashMap<u32, SubjectDataOuter>) -> SubjectStats {
+    let mut stats = SubjectStats::default();
+    for subject in map.values() {
+        stats.total.add(&subject.data);
+        if let Some(level) = subject.data.level() {
+            stats.levels.entry(level).or_default().add(&subject.data);
+        }
+        match &subject.data {
+            SubjectData::Radical(data) => {
+                stats.meanings += data.meanings.len();
+            }
+            SubjectData::Kanji(data) => {
+                stats.meanings += data.meanings.len();
+                stats.readings += data.readings.len();
+            }
+            SubjectData::Vocabulary(data) => {
+                stats.meanings += data.meanings.len();
+                stats.readings += data.readings.len();
+                stats.context_sentences += data.context_sentences.len();
+                for reading in &data.readings {
+                    match reading.composition {
+                        Some(ReadingComposition::Regular) => stats.regular_readings += 1,
+                        Some(ReadingComposition::SoundChange) => stats.sound_change_readings += 1,
+                        Some(ReadingComposition::Jukujikun) => stats.jukujikun_readings += 1,
+                        None => {}
+                    }
+                }
+            }
+            SubjectData::KanaVocabulary(_) => {}
+        }
+    }
+    stats
+}
+
+pub fn print_stats(stats: &SubjectStats) {
+    println!(
+        "{:>5} {:>8} {:>8} {:>10} {:>5} {:>8}",
+        "level", "radical", "kanji", "vocabulary", "kana", "total"
+    );
+    for (level, counts) in &stats.levels {
+        println!(
+            "{:>5} {:>8} {:>8} {:>10} {:>5} {:>8}",
+            level,
+            counts.radicals,
+            counts.kanji,
+            counts.vocabulary,
+            counts.kana_vocabulary,
+            counts.total()
+        );
+    }
+    println!(
+        "{:>5} {:>8} {:>8} {:>10} {:>5} {:>8}",
+        "all",
+        stats.total.radicals,
+        stats.total.kanji,
+        stats.total.vocabulary,
+        stats.total.kana_vocabulary,
+        stats.total.total()
+    );
+    println!();
+    println!("readings:          {}", stats.readings);
+    println!("meanings:          {}", stats.meanings);
+    println!("context sentences: {}", stats.context_sentences);
+    println!();
+    println!("regular readings:      {}", stats.regular_readings);
+    println!("sound change readings: {}", stats.sound_change_readings);
+    println!("jukujikun readings:    {}", stats.jukujikun_readings);
+}
@@ -0,0 +1,397 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::RangeInclusive,
+};
+
+use itertools::Itertools;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use japtools_core::schema::{KanjiData, RadicalData, VocabularyData};
+
+const OUTER_FIELDS: &[&str] = &["id", "object", "url", "data_updated_at", "data"];
+const UNTYPED_SUBJECTS: &[&str] = &["kana_vocabulary"];
+const EXAMPLES: usize = 5;
+// String fields the model keeps as plain strings or enums, with the values WaniKani documents
+const ENUM_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "data.auxiliary_meanings[].type",
+        &["whitelist", "blacklist"],
+    ),
+    (
+        "data.character_images[].content_type",
+        &["image/svg+xml", "image/png"],
+    ),
+    ("data.readings[].type", &["onyomi", "kunyomi", "nanori"]),
+    (
+        "data.pronunciation_audios[].content_type",
+        &["audio/mpeg", "audio/ogg", "audio/webm"],
+    ),
+    (
+        "data.pronunciation_audios[].metadata.gender",
+        &["male", "female"],
+    ),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Finding {
+    pub count: usize,
+    pub examples: Vec<u32>,
+}
+
+impl Finding {
+    fn add(&mut self, subject_id: u32) {
+        self.count += 1;
+        if self.examples.len() < EXAMPLES {
+            self.examples.push(subject_id);
+        }
+    }
+}
+
+// Everything is keyed by subject type first, then by field path, value or error
+#[derive(Debug, Clone, Default)]
+pub struct StrictReport {
+    pub unknown_fields: BTreeMap<String, BTreeMap<String, Finding>>,
+    pub unexpected_values: BTreeMap<String, BTreeMap<String, Finding>>,
+    pub errors: BTreeMap<String, BTreeMap<String, Finding>>,
+    pub untyped: BTreeMap<String, Finding>,
+}
+
+impl StrictReport {
+    // Untyped subjects are already known to be missing from the model
+    pub fn findings(&self) -> usize {
+        self.unknown_fields
+            .values()
+            .map(BTreeMap::len)
+            .sum::<usize>()
+            + self
+                .unexpected_values
+                .values()
+                .map(BTreeMap::len)
+                .sum::<usize>()
+            + self.errors.values().map(BTreeMap::len).sum::<usize>()
+    }
+
+    fn record(
+        map: &mut BTreeMap<String, BTreeMap<String, Finding>>,
+        subject_type: &str,
+        key: String,
+        subject_id: u32,
+    ) {
+        map.entry(subject_type.to_string())
+            .or_default()
+            .entry(key)
+            .or_default()
+            .add(subject_id);
+    }
+}
+
+// readings[3].type -> readings[].type so the same field groups across subjects
+fn generic_path(path: &str) -> String {
+    let mut out = String::new();
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                out.push(c);
+            }
+            ']' => {
+                in_index = false;
+                out.push(c);
+            }
+            _ if in_index => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+// Every object key in a value, with array indices dropped
+fn known_paths(value: &Value, prefix: &str, paths: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = join_path(prefix, key);
+                known_paths(value, &path, paths);
+                paths.insert(path);
+            }
+        }
+        Value::Array(array) => {
+            let path = format!("{}[]", prefix);
+            for value in array {
+                known_paths(value, &path, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Raw fields no round trip kept, only the outermost unknown key of a subtree is reported
+fn unknown_paths(
+    raw: &Value,
+    prefix: &str,
+    known: &BTreeSet<String>,
+    paths: &mut BTreeSet<String>,
+) {
+    match raw {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = join_path(prefix, key);
+                if known.contains(&path) {
+                    unknown_paths(value, &path, known, paths);
+                } else {
+                    paths.insert(path);
+                }
+            }
+        }
+        Value::Array(array) => {
+            let path = format!("{}[]", prefix);
+            for value in array {
+                unknown_paths(value, &path, known, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Values of ENUM_FIELDS outside the documented set, as `path = value`
+fn unexpected_values(raw: &Value, prefix: &str, values: &mut BTreeSet<String>) {
+    match raw {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = join_path(prefix, key);
+                let allowed = ENUM_FIELDS.iter().find(|(field, _)| *field == path);
+                match (allowed, value.as_str()) {
+                    (Some((_, allowed)), Some(x)) if allowed.contains(&x) => {}
+                    (Some(_), _) => {
+                        values.insert(format!("{} = {}", path, value));
+                    }
+                    (None, _) => unexpected_values(value, &path, values),
+                }
+            }
+        }
+        Value::Array(array) => {
+            let path = format!("{}[]", prefix);
+            for value in array {
+                unexpected_values(value, &path, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn round_trip<T: DeserializeOwned + Serialize>(data: &Value) -> Result<Value, String> {
+    let parsed: T = serde_path_to_error::deserialize(data)
+        .map_err(|e| format!("{}: {}", generic_path(&e.path().to_string()), e.inner()))?;
+    serde_json::to_value(parsed).map_err(|e| e.to_string())
+}
+
+// Deserialization stops at the first error, so unknown keys are found by walking the raw
+// payload against every field a subject of the same type kept through a round trip
+pub fn strict_check(raw: &HashMap<u32, Value>, levels: &RangeInclusive<u32>) -> StrictReport {
+    let mut report = StrictReport::default();
+    let mut known: HashMap<&str, BTreeSet<String>> = HashMap::new();
+    let mut checked = Vec::new();
+
+    for (id, subject) in raw.iter().sorted_by_key(|(id, _)| **id) {
+        let level = subject.pointer("/data/level").and_then(Value::as_u64);
+        if matches!(level, Some(level) if !levels.contains(&(level as u32))) {
+            continue;
+        }
+        let subject_type = subject
+            .get("object")
+            .and_then(Value::as_str)
+            .unwrap_or("<missing object>");
+
+        if let Some(outer) = subject.as_object() {
+            for key in outer.keys().filter(|x| !OUTER_FIELDS.contains(&x.as_str())) {
+                StrictReport::record(&mut report.unknown_fields, subject_type, key.clone(), *id);
+            }
+        }
+        let data = subject.get("data").unwrap_or(&Value::Null);
+        let mut values = BTreeSet::new();
+        unexpected_values(data, "data", &mut values);
+        for value in values {
+            StrictReport::record(&mut report.unexpected_values, subject_type, value, *id);
+        }
+
+        let round_tripped = match subject_type {
+            "radical" => round_trip::<RadicalData>(data),
+            "kanji" => round_trip::<KanjiData>(data),
+            "vocabulary" => round_trip::<VocabularyData>(data),
+            _ if UNTYPED_SUBJECTS.contains(&subject_type) => {
+                report
+                    .untyped
+                    .entry(subject_type.to_string())
+                    .or_default()
+                    .add(*id);
+                continue;
+            }
+            _ => Err("unknown subject type".to_string()),
+        };
+
+        match round_tripped {
+            Ok(round_tripped) => known_paths(
+                &round_tripped,
+                "data",
+                known.entry(subject_type).or_default(),
+            ),
+            Err(e) => StrictReport::record(&mut report.errors, subject_type, e, *id),
+        }
+        checked.push((*id, subject_type, data));
+    }
+
+    for (id, subject_type, data) in checked {
+        // Without a single subject that deserializes there is nothing to compare against
+        let known = match known.get(subject_type) {
+            Some(known) => known,
+            None => continue,
+        };
+        let mut paths = BTreeSet::new();
+        unknown_paths(data, "data", known, &mut paths);
+        for path in paths {
+            StrictReport::record(&mut report.unknown_fields, subject_type, path, id);
+        }
+    }
+
+    report
+}
+
+pub fn print_report(report: &StrictReport) {
+    let examples = |finding: &Finding| {
+        format!(
+            "{} subjects, e.g. {}",
+            finding.count,
+            finding.examples.iter().join(", ")
+        )
+    };
+    for (subject_type, fields) in &report.unknown_fields {
+        for (path, finding) in fields {
+            println!(
+                "{}: unknown field {} ({})",
+                subject_type,
+                path,
+                examples(finding)
+            );
+        }
+    }
+    for (subject_type, values) in &report.unexpected_values {
+        for (value, finding) in values {
+            println!(
+                "{}: unexpected value {} ({})",
+                subject_type,
+                value,
+                examples(finding)
+            );
+        }
+    }
+    for (subject_type, errors) in &report.errors {
+        for (error, finding) in errors {
+            println!(
+                "{}: does not deserialize: {} ({})",
+                subject_type,
+                error,
+                examples(finding)
+            );
+        }
+    }
+    for (subject_type, finding) in &report.untyped {
+        println!(
+            "{}: not modelled in schema.rs, kept as raw JSON ({})",
+            subject_type,
+            examples(finding)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn subject(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn keys(findings: &BTreeMap<String, Finding>) -> Vec<&str> {
+        findings.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn reports_extra_fields_and_unexpected_values() {
+        let kanji = subject(include_str!(
+            "../../japtools-core/tests/fixtures/subjects/kanji.json"
+        ));
+        let vocabulary = subject(include_str!(
+            "../../japtools-core/tests/fixtures/subjects/vocabulary.json"
+        ));
+
+        let mut extra = kanji.clone();
+        extra["id"] = json!(441);
+        extra["cached_at"] = json!("2023-12-01T00:00:00.000000Z");
+        extra["data"]["stroke_order_url"] = json!("https://example.com/441.svg");
+        extra["data"]["auxiliary_meanings"][0]["type"] = json!("greylist");
+        let mut bad_enum = kanji.clone();
+        bad_enum["id"] = json!(442);
+        bad_enum["data"]["readings"][0]["type"] = json!("rendaku");
+        // Outside the checked levels
+        let mut later = vocabulary.clone();
+        later["id"] = json!(2468);
+        later["data"]["level"] = json!(30);
+        later["data"]["stroke_order_url"] = json!("https://example.com/2468.svg");
+
+        let raw = HashMap::from([
+            (440, kanji),
+            (441, extra),
+            (442, bad_enum),
+            (2467, vocabulary),
+            (2468, later),
+            (
+                9177,
+                subject(include_str!(
+                    "../../japtools-core/tests/fixtures/subjects/kana_vocabulary.json"
+                )),
+            ),
+        ]);
+        let report = strict_check(&raw, &(1..=10));
+
+        assert_eq!(report.unknown_fields.keys().collect_vec(), ["kanji"]);
+        let unknown = &report.unknown_fields["kanji"];
+        assert_eq!(keys(unknown), ["cached_at", "data.stroke_order_url"]);
+        assert_eq!(unknown["data.stroke_order_url"].examples, [441]);
+
+        let values = &report.unexpected_values["kanji"];
+        assert_eq!(
+            keys(values),
+            [
+                "data.auxiliary_meanings[].type = \"greylist\"",
+                "data.readings[].type = \"rendaku\"",
+            ]
+        );
+        assert_eq!(values["data.readings[].type = \"rendaku\""].examples, [442]);
+
+        // The reading type is an enum in the model, so the subject does not deserialize
+        let errors = &report.errors["kanji"];
+        assert_eq!(errors.len(), 1);
+        let (error, finding) = errors.iter().next().unwrap();
+        assert!(
+            error.starts_with("readings[].type: unknown variant `rendaku`"),
+            "{}",
+            error
+        );
+        assert_eq!(finding.examples, [442]);
+
+        assert_eq!(report.untyped["kana_vocabulary"].examples, [9177]);
+        assert_eq!(report.findings(), 5);
+    }
+}
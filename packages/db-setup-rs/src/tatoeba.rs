@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    path::Path,
+};
+
+use itertools::Itertools;
+use japtools_core::{
+    schema::{ExampleSentence, SubjectData, SubjectDataOuter},
+    tokenize::SentenceToken,
+};
+use tracing::info;
+
+use crate::sentences::subject_dictionary;
+
+// Sentences around this many characters read best, longer ones are skipped entirely
+const IDEAL_LENGTH: usize = 15;
+const MAX_LENGTH: usize = 40;
+const PUNCTUATION: &str = "。、！？「」『』・…";
+
+pub struct TatoebaSentence {
+    pub id: u32,
+    pub ja: String,
+    pub en: String,
+    // Dictionary forms from jpn_indices.csv, empty without an index file
+    pub headwords: Vec<String>,
+}
+
+async fn read_file(path: &Path) -> Result<String, Box<dyn Error>> {
+    Ok(tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?)
+}
+
+// Sentence pairs are exported as jpn id, jpn text, eng id, eng text. A Japanese
+// sentence with several translations keeps the first one
+pub fn read_pairs(tsv: &str) -> Vec<TatoebaSentence> {
+    let mut seen = HashSet::new();
+    tsv.lines()
+        .filter_map(|line| match line.split('\t').collect_vec().as_slice() {
+            [id, ja, _, en, ..] => Some((id.parse::<u32>().ok()?, *ja, *en)),
+            _ => None,
+        })
+        .filter(|(id, ..)| seen.insert(*id))
+        .map(|(id, ja, en)| TatoebaSentence {
+            id,
+            ja: ja.to_string(),
+            en: en.to_string(),
+            headwords: vec![],
+        })
+        .collect()
+}
+
+// Index lines are sentence id, meaning id and words like 彼(かれ)[01]{彼の}~ separated by spaces
+pub fn read_indices(tsv: &str) -> HashMap<u32, Vec<String>> {
+    tsv.lines()
+        .filter_map(|line| match line.split('\t').collect_vec().as_slice() {
+            [id, _, words, ..] => Some((id.parse::<u32>().ok()?, *words)),
+            _ => None,
+        })
+        .map(|(id, words)| {
+            let headwords = words
+                .split_whitespace()
+                .filter_map(|word| word.split(['(', '[', '{', '~', '|']).next())
+                .filter(|x| !x.is_empty())
+                .map(str::to_string)
+                .collect();
+            (id, headwords)
+        })
+        .collect()
+}
+
+pub async fn read_tatoeba(
+    pairs: &Path,
+    indices: Option<&Path>,
+) -> Result<Vec<TatoebaSentence>, Box<dyn Error>> {
+    let mut sentences = read_pairs(&read_file(pairs).await?);
+    if let Some(indices) = indices {
+        let mut headwords = read_indices(&read_file(indices).await?);
+        for sentence in &mut sentences {
+            sentence.headwords = headwords.remove(&sentence.id).unwrap_or_default();
+        }
+    }
+    Ok(sentences)
+}
+
+// Share of the sentence covered by known subjects in percent, minus the distance from the ideal length
+fn score(ja: &str, tokens: &[SentenceToken]) -> i64 {
+    let length = ja
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_ascii_punctuation() && !PUNCTUATION.contains(*c))
+        .count();
+    let known = tokens
+        .iter()
+        .filter(|x| x.subject_id.is_some())
+        .map(|x| x.text.chars().count())
+        .sum::<usize>();
+    let coverage = (known * 100 / length.max(1)) as i64;
+    coverage - length.abs_diff(IDEAL_LENGTH) as i64
+}
+
+pub fn attach_examples(
+    map: &mut HashMap<u32, SubjectDataOuter>,
+    sentences: &[TatoebaSentence],
+    per_subject: usize,
+) {
+    let dictionary = subject_dictionary(map);
+    let mut kanji_ids = HashMap::new();
+    let mut vocabulary_ids = HashMap::new();
+    for subject in map.values() {
+        match &subject.data {
+            SubjectData::Kanji(data) => {
+                kanji_ids.insert(data.characters.clone(), subject.id);
+            }
+            SubjectData::Vocabulary(data) => {
+                vocabulary_ids.insert(data.characters.clone(), subject.id);
+            }
+            _ => {}
+        }
+    }
+
+    let tokens = sentences
+        .iter()
+        .map(|x| {
+            if x.ja.chars().count() <= MAX_LENGTH {
+                dictionary.tokenize(&x.ja)
+            } else {
+                vec![]
+            }
+        })
+        .collect_vec();
+
+    // subject id -> (score, sentence index)
+    let mut candidates: HashMap<u32, Vec<(i64, usize)>> = HashMap::new();
+    for (i, (sentence, tokens)) in sentences.iter().zip(&tokens).enumerate() {
+        if tokens.is_empty() {
+            continue;
+        }
+        let sentence_score = score(&sentence.ja, tokens);
+
+        let mut subjects = sentence
+            .ja
+            .chars()
+            .filter_map(|c| kanji_ids.get(&c.to_string()).copied())
+            .collect::<HashSet<u32>>();
+        // The index knows the dictionary form of conjugated words, plain matching is the fallback
+        if sentence.headwords.is_empty() {
+            subjects.extend(tokens.iter().filter_map(|x| x.subject_id));
+        } else {
+            subjects.extend(
+                sentence
+                    .headwords
+                    .iter()
+                    .filter_map(|x| vocabulary_ids.get(x).copied()),
+            );
+        }
+        for subject_id in subjects {
+            candidates
+                .entry(subject_id)
+                .or_default()
+                .push((sentence_score, i));
+        }
+    }
+
+    let mut attached = 0;
+    for (subject_id, mut ranked) in candidates {
+        ranked.sort_by_key(|(score, i)| (-score, sentences[*i].id));
+        let examples = ranked
+            .into_iter()
+            .take(per_subject)
+            .enumerate()
+            .map(|(rank, (_, i))| ExampleSentence {
+                tatoeba_id: sentences[i].id,
+                en: sentences[i].en.clone(),
+                ja: sentences[i].ja.clone(),
+                rank: rank as u32 + 1,
+                tokens: tokens[i].clone(),
+            })
+            .collect_vec();
+        attached += examples.len();
+        match map.get_mut(&subject_id).map(|x| &mut x.data) {
+            Some(SubjectData::Kanji(data)) => data.example_sentences = examples,
+            Some(SubjectData::Vocabulary(data)) => data.example_sentences = examples,
+            _ => {}
+        }
+    }
+    info!(
+        sentences = sentences.len(),
+        attached, "attached Tatoeba example sentences"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jmdict::read_jmdict, source::JMDICT_ID_OFFSET};
+
+    const PAIRS: &str = include_str!("../tests/fixtures/tatoeba_pairs.tsv");
+    const INDICES: &str = include_str!("../tests/fixtures/tatoeba_indices.csv");
+
+    fn sentences() -> Vec<TatoebaSentence> {
+        let mut sentences = read_pairs(PAIRS);
+        let mut headwords = read_indices(INDICES);
+        for sentence in &mut sentences {
+            sentence.headwords = headwords.remove(&sentence.id).unwrap_or_default();
+        }
+        sentences
+    }
+
+    fn examples(map: &HashMap<u32, SubjectDataOuter>, sequence: u32) -> Vec<(u32, u32)> {
+        match &map[&(JMDICT_ID_OFFSET + sequence)].data {
+            SubjectData::Vocabulary(data) => data
+                .example_sentences
+                .iter()
+                .map(|x| (x.tatoeba_id, x.rank))
+                .collect(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_first_translation() {
+        let sentences = read_pairs(PAIRS);
+        assert_eq!(
+            sentences.iter().map(|x| x.id).collect_vec(),
+            [100, 101, 102, 103, 104]
+        );
+        assert_eq!(sentences[0].ja, "学生です。");
+        assert_eq!(sentences[0].en, "I'm a student.");
+    }
+
+    #[test]
+    fn reads_index_headwords() {
+        let indices = read_indices(INDICES);
+        assert_eq!(indices.len(), 2);
+        assert_eq!(indices[&102], ["御飯", "を", "食べる"]);
+        assert_eq!(indices[&103], ["学校", "へ", "行く"]);
+    }
+
+    #[test]
+    fn ranks_examples_per_subject() {
+        let xml = include_str!("../tests/fixtures/jmdict.xml");
+        let mut map = read_jmdict(xml, &(1..=60)).unwrap();
+        attach_examples(&mut map, &sentences(), 2);
+
+        // The shorter sentence is covered more by 学生, the long one is never used
+        assert_eq!(examples(&map, 1311110), [(100, 1), (101, 2)]);
+        // Conjugated words are found through the index headwords
+        assert_eq!(examples(&map, 1358280), [(102, 1)]);
+        assert_eq!(examples(&map, 1578850), [(103, 1)]);
+
+        attach_examples(&mut map, &sentences(), 1);
+        assert_eq!(examples(&map, 1311110), [(100, 1)]);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    io::IsTerminal,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use tracing::{
+    field::{Field, Visit},
+    info, span, Subscriber,
+};
+use tracing_subscriber::{fmt, layer::Context, prelude::*, registry::LookupSpan, EnvFilter, Layer};
+
+const TIMED_SPANS: &[&str] = &["fetch_level", "upload_subject", "query", "batch"];
+const SLOWEST_QUERIES: usize = 10;
+
+static SHOW_PROGRESS: OnceLock<bool> = OnceLock::new();
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpanTiming {
+    pub name: &'static str,
+    pub fields: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct Timings {
+    spans: Mutex<Vec<SpanTiming>>,
+}
+
+#[derive(Default)]
+struct FieldString(String);
+
+impl Visit for FieldString {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = write!(self.0, "{}={:?}", field.name(), value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{}", value));
+    }
+}
+
+struct Started {
+    at: Instant,
+    fields: String,
+}
+
+struct TimingLayer {
+    timings: Arc<Timings>,
+}
+
+impl<S> Layer<S> for TimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if !TIMED_SPANS.contains(&attrs.metadata().name()) {
+            return;
+        }
+        let mut fields = FieldString::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started {
+                at: Instant::now(),
+                fields: fields.0,
+            });
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let extensions = span.extensions();
+        if let Some(started) = extensions.get::<Started>() {
+            self.timings.spans.lock().unwrap().push(SpanTiming {
+                name: span.metadata().name(),
+                fields: started.fields.clone(),
+                duration: started.at.elapsed(),
+            });
+        }
+    }
+}
+
+pub fn init(format: LogFormat) -> Arc<Timings> {
+    let timings = Arc::new(Timings::default());
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("dbsetup=info"));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(TimingLayer {
+            timings: timings.clone(),
+        });
+    match format {
+        LogFormat::Text => registry
+            .with(fmt::layer().with_writer(std::io::stderr))
+            .init(),
+        LogFormat::Json => registry
+            .with(fmt::layer().json().with_writer(std::io::stderr))
+            .init(),
+    }
+
+    let _ = SHOW_PROGRESS.set(format == LogFormat::Text && std::io::stderr().is_terminal());
+    timings
+}
+
+// Progress bars only make sense for humans, CI gets the structured logs instead
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if *SHOW_PROGRESS.get().unwrap_or(&false) {
+        ProgressBar::new(len)
+    } else {
+        ProgressBar::hidden()
+    }
+}
+
+pub fn log_summary(timings: &Timings, started: Instant) {
+    let spans = timings.spans.lock().unwrap();
+    let by_name: BTreeMap<&str, Vec<&SpanTiming>> = spans
+        .iter()
+        .map(|x| (x.name, x))
+        .into_group_map()
+        .into_iter()
+        .collect();
+    for (name, entries) in &by_name {
+        let total: Duration = entries.iter().map(|x| x.duration).sum();
+        let slowest = entries.iter().map(|x| x.duration).max().unwrap_or_default();
+        info!(
+            span = name,
+            count = entries.len(),
+            total_ms = total.as_millis() as u64,
+            max_ms = slowest.as_millis() as u64,
+            "timing summary"
+        );
+    }
+    for timing in spans
+        .iter()
+        .filter(|x| x.name == "query" || x.name == "batch")
+        .sorted_by_key(|x| std::cmp::Reverse(x.duration))
+        .take(SLOWEST_QUERIES)
+    {
+        info!(
+            span = timing.name,
+            duration_ms = timing.duration.as_millis() as u64,
+            fields = %timing.fields,
+            "slow query"
+        );
+    }
+    info!(
+        duration_ms = started.elapsed().as_millis() as u64,
+        "finished"
+    );
+}
@@ -2,12 +2,22 @@ use std::{collections::HashMap, error::Error, sync::Arc};
 use tokio_stream::{self as stream};
 
 use futures_util::StreamExt;
-use indicatif::ProgressBar;
 use itertools::Itertools;
+use prisma_client_rust::QueryError;
+use tracing::{error, info, info_span, warn, Instrument, Span};
+
+use japtools_core::{
+    furigana::ReadingComposition,
+    normalize::{meaning_keys, reading_keys, vocabulary_reading_keys},
+    pitch::{pitch_pattern, PitchPattern},
+    schema::{ReadingType, SubjectData, SubjectDataOuter},
+};
 
 use crate::{
     db::{self, SubjectType},
-    schema::{CharacterImageMetadata, ReadingType, SubjectData, SubjectDataOuter},
+    preflight::connect,
+    rows::{json_column, largest_png_url, stroke_count, subject_media, MediaKind},
+    telemetry::progress_bar,
 };
 
 fn data_to_type(data: &SubjectData) -> SubjectType {
@@ -15,488 +25,617 @@ fn data_to_type(data: &SubjectData) -> SubjectType {
         SubjectData::Kanji(_) => SubjectType::Kanji,
         SubjectData::Radical(_) => SubjectType::Radical,
         SubjectData::Vocabulary(_) => SubjectType::Vocabulary,
-        _ => panic!()
+        _ => panic!(),
     }
 }
 
-pub async fn upload_to_db(map: HashMap<u32, SubjectDataOuter>) -> Result<(), Box<dyn Error>> {
-    let client = Arc::new(
-        db::PrismaClient::_builder()
-            .build()
-            .await
-            .expect("Failed to create client"),
-    );
-    let mut tasks = stream::iter(map.clone().iter().map(|x| x.1.clone()).collect_vec())
-        .map(|subject| {
-            let client = client.clone();
-            tokio::spawn(async move {
-                let subject_index = client
-                    .subject_index()
-                    .find_unique(db::subject_index::subject_id::equals(subject.id as i32))
-                    .exec()
-                    .await
-                    .unwrap();
+fn query_span(table: &'static str, operation: &'static str, subject_id: u32) -> Span {
+    info_span!("query", table, operation, subject_id)
+}
 
-                if let Some(subject_index) = subject_index {
-                    if subject_index.subject_type != data_to_type(&subject.data) {
-                        client
-                            .subject_index()
-                            .delete(db::subject_index::subject_id::equals(subject.id as i32))
-                            .exec()
-                            .await
-                            .unwrap();
+fn batch_span(table: &'static str, operation: &'static str, subject_id: u32) -> Span {
+    info_span!("batch", table, operation, subject_id)
+}
 
-                        match subject_index.subject_type {
-                            db::SubjectType::Radical => {
-                                match client
-                                    .radical_subject()
-                                    .delete(db::radical_subject::id::equals(
-                                        subject_index.subject_id as i32,
-                                    ))
-                                    .exec()
-                                    .await
-                                {
-                                    Ok(_) => (),
-                                    Err(e) => println!("Error deleting radical: {}", e),
-                                }
-                            }
-                            db::SubjectType::Kanji => {
-                                match client
-                                    .kanji_subject()
-                                    .delete(db::kanji_subject::id::equals(
-                                        subject_index.subject_id as i32,
-                                    ))
-                                    .exec()
-                                    .await
-                                {
-                                    Ok(_) => (),
-                                    Err(e) => println!("Error deleting kanji: {}", e),
-                                }
-                            }
-                            db::SubjectType::Vocabulary => {
-                                match client
-                                    .vocabulary_subject()
-                                    .delete(db::vocabulary_subject::id::equals(
-                                        subject_index.subject_id as i32,
-                                    ))
-                                    .exec()
-                                    .await
-                                {
-                                    Ok(_) => (),
-                                    Err(e) => println!("Error deleting vocabulary: {}", e),
-                                }
-                            }
-                        }
-                    }
-                }
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UploadReport {
+    pub uploaded: usize,
+    pub failed: usize,
+}
 
+async fn upload_subject(
+    client: &db::PrismaClient,
+    subject: &SubjectDataOuter,
+) -> Result<(), QueryError> {
+    let subject_index = client
+        .subject_index()
+        .find_unique(db::subject_index::subject_id::equals(subject.id as i32))
+        .exec()
+        .instrument(query_span("subject_index", "find_unique", subject.id))
+        .await?;
+
+    // A subject that changes type keeps its index row, its reviews and progress point at it
+    if let Some(subject_index) = subject_index {
+        // Dependent meanings, readings and sentences cascade with the subject
+        match subject_index.subject_type {
+            db::SubjectType::Radical => {
+                match client
+                    .radical_subject()
+                    .delete(db::radical_subject::id::equals(
+                        subject_index.subject_id as i32,
+                    ))
+                    .exec()
+                    .instrument(query_span("radical_subject", "delete", subject.id))
+                    .await
                 {
-                    let params = vec![
-                        db::subject_index::readings::set(match &subject.data {
-                            SubjectData::Radical(_) => vec![],
-                            SubjectData::Kanji(data) => data
-                                .readings
-                                .iter()
-                                .map(|x| x.reading.clone())
-                                .collect_vec(),
-                            SubjectData::Vocabulary(data) => data
-                                .readings
+                    Ok(_) => (),
+                    Err(e) => warn!(error = %e, "failed to delete radical"),
+                }
+            }
+            db::SubjectType::Kanji => {
+                match client
+                    .kanji_subject()
+                    .delete(db::kanji_subject::id::equals(
+                        subject_index.subject_id as i32,
+                    ))
+                    .exec()
+                    .instrument(query_span("kanji_subject", "delete", subject.id))
+                    .await
+                {
+                    Ok(_) => (),
+                    Err(e) => warn!(error = %e, "failed to delete kanji"),
+                }
+            }
+            db::SubjectType::Vocabulary => {
+                match client
+                    .vocabulary_subject()
+                    .delete(db::vocabulary_subject::id::equals(
+                        subject_index.subject_id as i32,
+                    ))
+                    .exec()
+                    .instrument(query_span("vocabulary_subject", "delete", subject.id))
+                    .await
+                {
+                    Ok(_) => (),
+                    Err(e) => warn!(error = %e, "failed to delete vocabulary"),
+                }
+            }
+        }
+    }
+
+    {
+        let params = vec![
+            db::subject_index::subject_type::set(data_to_type(&subject.data)),
+            db::subject_index::readings::set(match &subject.data {
+                SubjectData::Radical(_) => vec![],
+                SubjectData::Kanji(data) => data
+                    .readings
+                    .iter()
+                    .map(|x| x.reading.clone())
+                    .collect_vec(),
+                SubjectData::Vocabulary(data) => data
+                    .readings
+                    .iter()
+                    .map(|x| x.reading.clone())
+                    .collect_vec(),
+                SubjectData::KanaVocabulary(_) => vec![],
+            }),
+            db::subject_index::meanings::set(match &subject.data {
+                SubjectData::Radical(_) => vec![],
+                SubjectData::Kanji(data) => data
+                    .meanings
+                    .iter()
+                    .map(|x| x.meaning.clone())
+                    .collect_vec(),
+                SubjectData::Vocabulary(data) => data
+                    .meanings
+                    .iter()
+                    .map(|x| x.meaning.clone())
+                    .collect_vec(),
+                SubjectData::KanaVocabulary(_) => vec![],
+            }),
+            db::subject_index::reading_keys::set(match &subject.data {
+                SubjectData::Radical(_) => vec![],
+                SubjectData::Kanji(data) => {
+                    reading_keys(data.readings.iter().map(|x| x.reading.as_str()))
+                }
+                SubjectData::Vocabulary(data) => vocabulary_reading_keys(&data.readings),
+                SubjectData::KanaVocabulary(_) => vec![],
+            }),
+            db::subject_index::meaning_keys::set(match &subject.data {
+                SubjectData::Radical(data) => {
+                    meaning_keys(data.meanings.iter().map(|x| x.meaning.as_str()))
+                }
+                SubjectData::Kanji(data) => {
+                    meaning_keys(data.meanings.iter().map(|x| x.meaning.as_str()))
+                }
+                SubjectData::Vocabulary(data) => {
+                    meaning_keys(data.meanings.iter().map(|x| x.meaning.as_str()))
+                }
+                SubjectData::KanaVocabulary(_) => vec![],
+            }),
+        ];
+        client
+            .subject_index()
+            .upsert(
+                db::subject_index::subject_id::equals(subject.id as i32),
+                db::subject_index::create(
+                    match &subject.data {
+                        SubjectData::Radical(_) => db::SubjectType::Radical,
+                        SubjectData::Kanji(_) => db::SubjectType::Kanji,
+                        SubjectData::Vocabulary(_) => db::SubjectType::Vocabulary,
+                        _ => panic!(),
+                    },
+                    subject.id as i32,
+                    match &subject.data {
+                        SubjectData::Radical(data) => data.level as i32,
+                        SubjectData::Kanji(data) => data.level as i32,
+                        SubjectData::Vocabulary(data) => data.level as i32,
+                        _ => panic!(),
+                    },
+                    params.clone(),
+                ),
+                params,
+            )
+            .exec()
+            .instrument(query_span("subject_index", "upsert", subject.id))
+            .await?;
+    }
+    match &subject.data {
+        SubjectData::Kanji(kanji_data) => {
+            client
+                .kanji_subject()
+                .create(
+                    subject.id as i32,
+                    kanji_data.characters.clone(),
+                    kanji_data.lesson_position as i32,
+                    kanji_data.level as i32,
+                    kanji_data.meaning_mnemonic.clone(),
+                    kanji_data.reading_hint.clone(),
+                    kanji_data.reading_mnemonic.clone(),
+                    vec![
+                        db::kanji_subject::meaning_hint::set(kanji_data.meaning_hint.clone()),
+                        db::kanji_subject::amalgamation_subject_ids::set(
+                            kanji_data
+                                .amalgamation_subject_ids
                                 .iter()
-                                .map(|x| x.reading.clone())
-                                .collect_vec(),
-                            SubjectData::Kana_Vocabulary(_) => vec![],
-                        }),
-                        db::subject_index::meanings::set(match &subject.data {
-                            SubjectData::Radical(_) => vec![],
-                            SubjectData::Kanji(data) => data
-                                .meanings
+                                .map(|x| *x as i32)
+                                .collect(),
+                        ),
+                        db::kanji_subject::component_subject_ids::set(
+                            kanji_data
+                                .component_subject_ids
                                 .iter()
-                                .map(|x| x.meaning.clone())
-                                .collect_vec(),
-                            SubjectData::Vocabulary(data) => data
-                                .meanings
+                                .map(|x| *x as i32)
+                                .collect(),
+                        ),
+                        db::kanji_subject::visually_similar_subject_ids::set(
+                            kanji_data
+                                .visually_similar_subject_ids
                                 .iter()
-                                .map(|x| x.meaning.clone())
-                                .collect_vec(),
-                            SubjectData::Kana_Vocabulary(_) => vec![],
-                        }),
-                    ];
-                    client
-                        .subject_index()
-                        .upsert(
-                            db::subject_index::subject_id::equals(subject.id as i32),
-                            db::subject_index::create(
-                                match &subject.data {
-                                    SubjectData::Radical(_) => db::SubjectType::Radical,
-                                    SubjectData::Kanji(_) => db::SubjectType::Kanji,
-                                    SubjectData::Vocabulary(_) => db::SubjectType::Vocabulary,
-                                    _ => panic!(),
-                                },
-                                subject.id as i32,
-                                match &subject.data {
-                                    SubjectData::Radical(data) => data.level as i32,
-                                    SubjectData::Kanji(data) => data.level as i32,
-                                    SubjectData::Vocabulary(data) => data.level as i32,
-                                    _ => panic!(),
+                                .map(|x| *x as i32)
+                                .collect(),
+                        ),
+                        db::kanji_subject::stroke_count::set(
+                            stroke_count(&kanji_data.stroke_paths).map(|x| x as i32),
+                        ),
+                        db::kanji_subject::stroke_paths::set(kanji_data.stroke_paths.clone()),
+                    ],
+                )
+                .exec()
+                .instrument(query_span("kanji_subject", "create", subject.id))
+                .await?;
+            client
+                .kanji_reading()
+                .create_many(
+                    kanji_data
+                        .readings
+                        .iter()
+                        .map(|reading| {
+                            db::kanji_reading::create_unchecked(
+                                reading.reading.clone(),
+                                match reading.reading_type {
+                                    ReadingType::Onyomi => db::ReadingType::Onyomi,
+                                    ReadingType::Kunyomi => db::ReadingType::Kunyomi,
+                                    ReadingType::Nanori => db::ReadingType::Nanori,
                                 },
-                                params.clone(),
-                            ),
-                            params,
-                        )
-                        .exec()
-                        .await
-                        .unwrap()
-                };
-                match &subject.data {
-                    _ => panic!(),
-                    SubjectData::Kanji(kanji_data) => {
-                        client
-                            .kanji_subject()
-                            .create(
-                                subject.id as i32,
-                                kanji_data.characters.clone(),
-                                kanji_data.lesson_position as i32,
-                                kanji_data.level as i32,
-                                kanji_data.meaning_mnemonic.clone(),
-                                kanji_data.reading_hint.clone(),
-                                kanji_data.reading_mnemonic.clone(),
+                                reading.primary,
+                                vec![db::kanji_reading::kanji_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
+                            )
+                        })
+                        .collect(),
+                )
+                .exec()
+                .instrument(batch_span("kanji_reading", "create_many", subject.id))
+                .await?;
+            client
+                .example_sentence()
+                .create_many(
+                    kanji_data
+                        .example_sentences
+                        .iter()
+                        .map(|example_sentence| {
+                            db::example_sentence::create_unchecked(
+                                example_sentence.tatoeba_id as i32,
+                                example_sentence.en.clone(),
+                                example_sentence.ja.clone(),
+                                example_sentence.rank as i32,
                                 vec![
-                                    db::kanji_subject::meaning_hint::set(
-                                        kanji_data.meaning_hint.clone(),
-                                    ),
-                                    db::kanji_subject::amalgamation_subject_ids::set(
-                                        kanji_data
-                                            .amalgamation_subject_ids
-                                            .iter()
-                                            .map(|x| *x as i32)
-                                            .collect(),
-                                    ),
-                                    db::kanji_subject::component_subject_ids::set(
-                                        kanji_data
-                                            .component_subject_ids
-                                            .iter()
-                                            .map(|x| *x as i32)
-                                            .collect(),
-                                    ),
-                                    db::kanji_subject::visually_similar_subject_ids::set(
-                                        kanji_data
-                                            .visually_similar_subject_ids
-                                            .iter()
-                                            .map(|x| *x as i32)
-                                            .collect(),
-                                    ),
+                                    db::example_sentence::kanji_subject_id::set(Some(
+                                        subject.id as i32,
+                                    )),
+                                    db::example_sentence::tokens::set(json_column(
+                                        &example_sentence.tokens,
+                                    )),
                                 ],
                             )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .kanji_reading()
-                            .create_many(
-                                kanji_data
-                                    .readings
-                                    .iter()
-                                    .map(|reading| {
-                                        db::kanji_reading::create_unchecked(
-                                            reading.reading.clone(),
-                                            match reading.reading_type {
-                                                ReadingType::Onyomi => db::ReadingType::Onyomi,
-                                                ReadingType::Kunyomi => db::ReadingType::Kunyomi,
-                                                ReadingType::Nanori => db::ReadingType::Nanori,
-                                            },
-                                            reading.primary,
-                                            vec![db::kanji_reading::kanji_subject_id::set(Some(
-                                                subject.id as i32,
-                                            ))],
-                                        )
-                                    })
-                                    .collect(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("example_sentence", "create_many", subject.id))
+                .await?;
 
-                        client
-                            .subject_meaning()
-                            .create_many(
-                                kanji_data
-                                    .meanings
-                                    .iter()
-                                    .map(|meaning| {
-                                        db::subject_meaning::create_unchecked(
-                                            meaning.accepted_answer,
-                                            meaning.meaning.clone(),
-                                            meaning.primary,
-                                            vec![db::subject_meaning::kanji_subject_id::set(Some(
-                                                subject.id as i32,
-                                            ))],
-                                        )
-                                    })
-                                    .collect_vec(),
+            client
+                .subject_meaning()
+                .create_many(
+                    kanji_data
+                        .meanings
+                        .iter()
+                        .map(|meaning| {
+                            db::subject_meaning::create_unchecked(
+                                meaning.accepted_answer,
+                                meaning.meaning.clone(),
+                                meaning.primary,
+                                vec![db::subject_meaning::kanji_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
                             )
-                            .exec()
-                            .await
-                            .unwrap();
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("subject_meaning", "create_many", subject.id))
+                .await?;
 
-                        client
-                            .auxiliary_meaning()
-                            .create_many(
-                                kanji_data
-                                    .auxiliary_meanings
-                                    .iter()
-                                    .map(|auxiliary_meaning| {
-                                        db::auxiliary_meaning::create_unchecked(
-                                            auxiliary_meaning.meaning.clone(),
-                                            auxiliary_meaning.meaning_type.clone(),
-                                            vec![db::auxiliary_meaning::kanji_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect(),
+            client
+                .auxiliary_meaning()
+                .create_many(
+                    kanji_data
+                        .auxiliary_meanings
+                        .iter()
+                        .map(|auxiliary_meaning| {
+                            db::auxiliary_meaning::create_unchecked(
+                                auxiliary_meaning.meaning.clone(),
+                                auxiliary_meaning.meaning_type.clone(),
+                                vec![db::auxiliary_meaning::kanji_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
                             )
-                            .exec()
-                            .await
-                            .unwrap();
-
-                        client
-                            .auxiliary_meaning()
-                            .create_many(
-                                kanji_data
-                                    .auxiliary_meanings
-                                    .iter()
-                                    .map(|auxiliary_meaning| {
-                                        db::auxiliary_meaning::create_unchecked(
-                                            auxiliary_meaning.meaning.clone(),
-                                            auxiliary_meaning.meaning_type.clone(),
-                                            vec![db::auxiliary_meaning::kanji_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect_vec(),
+                        })
+                        .collect(),
+                )
+                .exec()
+                .instrument(batch_span("auxiliary_meaning", "create_many", subject.id))
+                .await?;
+        }
+        SubjectData::Radical(radical_data) => {
+            client
+                .radical_subject()
+                .create(
+                    subject.id as i32,
+                    radical_data.lesson_position as i32,
+                    radical_data.level as i32,
+                    radical_data.meaning_mnemonic.clone(),
+                    vec![
+                        db::radical_subject::characters::set(radical_data.characters.clone()),
+                        db::radical_subject::amalgamation_subject_ids::set(
+                            radical_data
+                                .amalgamation_subject_ids
+                                .iter()
+                                .map(|x| *x as i32)
+                                .collect(),
+                        ),
+                        db::radical_subject::image_url::set(largest_png_url(
+                            &radical_data.character_images,
+                        )),
+                    ],
+                )
+                .exec()
+                .instrument(query_span("radical_subject", "create", subject.id))
+                .await?;
+            client
+                .auxiliary_meaning()
+                .create_many(
+                    radical_data
+                        .auxiliary_meanings
+                        .iter()
+                        .map(|auxiliary_meaning| {
+                            db::auxiliary_meaning::create_unchecked(
+                                auxiliary_meaning.meaning.clone(),
+                                auxiliary_meaning.meaning_type.clone(),
+                                vec![db::auxiliary_meaning::radical_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
+                            )
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("auxiliary_meaning", "create_many", subject.id))
+                .await?;
+            client
+                .subject_meaning()
+                .create_many(
+                    radical_data
+                        .meanings
+                        .iter()
+                        .map(|meaning| {
+                            db::subject_meaning::create_unchecked(
+                                meaning.accepted_answer,
+                                meaning.meaning.clone(),
+                                meaning.primary,
+                                vec![db::subject_meaning::radical_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
+                            )
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("subject_meaning", "create_many", subject.id))
+                .await?;
+        }
+        SubjectData::Vocabulary(vocabulary_data) => {
+            client
+                .vocabulary_subject()
+                .create(
+                    subject.id as i32,
+                    vocabulary_data.characters.clone(),
+                    vocabulary_data.lesson_position as i32,
+                    vocabulary_data.level as i32,
+                    vocabulary_data.meaning_mnemonic.clone(),
+                    vocabulary_data.reading_mnemonic.clone(),
+                    vec![db::vocabulary_subject::component_subject_ids::set(
+                        vocabulary_data
+                            .component_subject_ids
+                            .iter()
+                            .map(|x| *x as i32)
+                            .collect(),
+                    )],
+                )
+                .exec()
+                .instrument(query_span("vocabulary_subject", "create", subject.id))
+                .await?;
+            client
+                .auxiliary_meaning()
+                .create_many(
+                    vocabulary_data
+                        .auxiliary_meanings
+                        .iter()
+                        .map(|auxiliary_meaning| {
+                            db::auxiliary_meaning::create_unchecked(
+                                auxiliary_meaning.meaning.clone(),
+                                auxiliary_meaning.meaning_type.clone(),
+                                vec![db::auxiliary_meaning::vocabulary_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
                             )
-                            .exec()
-                            .await
-                            .unwrap();
-                    }
-                    SubjectData::Radical(radical_data) => {
-                        client
-                            .radical_subject()
-                            .create(
-                                subject.id as i32,
-                                radical_data.lesson_position as i32,
-                                radical_data.level as i32,
-                                radical_data.meaning_mnemonic.clone(),
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("auxiliary_meaning", "create_many", subject.id))
+                .await?;
+            client
+                .context_sentence()
+                .create_many(
+                    vocabulary_data
+                        .context_sentences
+                        .iter()
+                        .map(|context_sentence| {
+                            db::context_sentence::create_unchecked(
+                                context_sentence.en.clone(),
+                                context_sentence.ja.clone(),
                                 vec![
-                                    db::radical_subject::characters::set(
-                                        radical_data.characters.clone(),
+                                    db::context_sentence::vocabulary_subject_id::set(Some(
+                                        subject.id as i32,
+                                    )),
+                                    db::context_sentence::tokens::set(json_column(
+                                        &context_sentence.tokens,
+                                    )),
+                                ],
+                            )
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("context_sentence", "create_many", subject.id))
+                .await?;
+            client
+                .example_sentence()
+                .create_many(
+                    vocabulary_data
+                        .example_sentences
+                        .iter()
+                        .map(|example_sentence| {
+                            db::example_sentence::create_unchecked(
+                                example_sentence.tatoeba_id as i32,
+                                example_sentence.en.clone(),
+                                example_sentence.ja.clone(),
+                                example_sentence.rank as i32,
+                                vec![
+                                    db::example_sentence::vocabulary_subject_id::set(Some(
+                                        subject.id as i32,
+                                    )),
+                                    db::example_sentence::tokens::set(json_column(
+                                        &example_sentence.tokens,
+                                    )),
+                                ],
+                            )
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("example_sentence", "create_many", subject.id))
+                .await?;
+            client
+                .subject_meaning()
+                .create_many(
+                    vocabulary_data
+                        .meanings
+                        .iter()
+                        .map(|meaning| {
+                            db::subject_meaning::create_unchecked(
+                                meaning.accepted_answer,
+                                meaning.meaning.clone(),
+                                meaning.primary,
+                                vec![db::subject_meaning::vocabulary_subject_id::set(Some(
+                                    subject.id as i32,
+                                ))],
+                            )
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("subject_meaning", "create_many", subject.id))
+                .await?;
+            client
+                .vocabulary_reading()
+                .create_many(
+                    vocabulary_data
+                        .readings
+                        .iter()
+                        .map(|reading| {
+                            db::vocabulary_reading::create_unchecked(
+                                reading.reading.clone(),
+                                reading.primary,
+                                reading.accepted_answer,
+                                vec![
+                                    db::vocabulary_reading::vocabulary_subject_id::set(Some(
+                                        subject.id as i32,
+                                    )),
+                                    db::vocabulary_reading::pitch_accents::set(
+                                        reading.pitch_accents.iter().map(|x| *x as i32).collect(),
                                     ),
-                                    db::radical_subject::amalgamation_subject_ids::set(
-                                        radical_data
-                                            .amalgamation_subject_ids
+                                    db::vocabulary_reading::pitch_patterns::set(
+                                        reading
+                                            .pitch_accents
                                             .iter()
-                                            .map(|x| *x as i32)
-                                            .collect(),
-                                    ),
-                                    db::radical_subject::image_url::set({
-                                        let v =
-                                            &radical_data.character_images.iter().max_by_key(|x| {
-                                                match &x.metadata {
-                                                    CharacterImageMetadata::Png(data) => {
-                                                        let dimensions = {
-                                                            let split = data.dimensions.split('x');
-                                                            let mut split = split
-                                                                .map(|x| x.parse::<u32>().unwrap());
-                                                            (
-                                                                split.next().unwrap(),
-                                                                split.next().unwrap(),
-                                                            )
-                                                        };
-                                                        dimensions.0 * dimensions.1
-                                                    }
-                                                    _ => 0,
+                                            .map(|x| match pitch_pattern(&reading.reading, *x) {
+                                                PitchPattern::Heiban => db::PitchPattern::Heiban,
+                                                PitchPattern::Atamadaka => {
+                                                    db::PitchPattern::Atamadaka
                                                 }
-                                            });
-                                        let url = match v {
-                                            Some(x) => match &x.metadata {
-                                                CharacterImageMetadata::Png(_) => {
-                                                    Some(x.url.clone())
+                                                PitchPattern::Nakadaka => {
+                                                    db::PitchPattern::Nakadaka
                                                 }
-                                                _ => None,
-                                            },
-                                            None => None,
-                                        };
-
-                                        url
-                                    }),
+                                                PitchPattern::Odaka => db::PitchPattern::Odaka,
+                                            })
+                                            .collect(),
+                                    ),
+                                    db::vocabulary_reading::furigana::set(json_column(
+                                        &reading.furigana,
+                                    )),
+                                    db::vocabulary_reading::composition::set(
+                                        reading.composition.map(|x| match x {
+                                            ReadingComposition::Regular => {
+                                                db::ReadingComposition::Regular
+                                            }
+                                            ReadingComposition::SoundChange => {
+                                                db::ReadingComposition::SoundChange
+                                            }
+                                            ReadingComposition::Jukujikun => {
+                                                db::ReadingComposition::Jukujikun
+                                            }
+                                        }),
+                                    ),
                                 ],
                             )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .auxiliary_meaning()
-                            .create_many(
-                                radical_data
-                                    .auxiliary_meanings
-                                    .iter()
-                                    .map(|auxiliary_meaning| {
-                                        db::auxiliary_meaning::create_unchecked(
-                                            auxiliary_meaning.meaning.clone(),
-                                            auxiliary_meaning.meaning_type.clone(),
-                                            vec![db::auxiliary_meaning::radical_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .subject_meaning()
-                            .create_many(
-                                radical_data
-                                    .meanings
-                                    .iter()
-                                    .map(|meaning| {
-                                        db::subject_meaning::create_unchecked(
-                                            meaning.accepted_answer,
-                                            meaning.meaning.clone(),
-                                            meaning.primary,
-                                            vec![db::subject_meaning::radical_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                    }
-                    SubjectData::Vocabulary(vocabulary_data) => {
-                        client
-                            .vocabulary_subject()
-                            .create(
-                                subject.id as i32,
-                                vocabulary_data.characters.clone(),
-                                vocabulary_data.lesson_position as i32,
-                                vocabulary_data.level as i32,
-                                vocabulary_data.meaning_mnemonic.clone(),
-                                vocabulary_data.reading_mnemonic.clone(),
-                                vec![db::vocabulary_subject::component_subject_ids::set(
-                                    vocabulary_data
-                                        .component_subject_ids
-                                        .iter()
-                                        .map(|x| *x as i32)
-                                        .collect(),
-                                )],
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .auxiliary_meaning()
-                            .create_many(
-                                vocabulary_data
-                                    .auxiliary_meanings
-                                    .iter()
-                                    .map(|auxiliary_meaning| {
-                                        db::auxiliary_meaning::create_unchecked(
-                                            auxiliary_meaning.meaning.clone(),
-                                            auxiliary_meaning.meaning_type.clone(),
-                                            vec![
-                                                db::auxiliary_meaning::vocabulary_subject_id::set(
-                                                    Some(subject.id as i32),
-                                                ),
-                                            ],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .context_sentence()
-                            .create_many(
-                                vocabulary_data
-                                    .context_sentences
-                                    .iter()
-                                    .map(|context_sentence| {
-                                        db::context_sentence::create_unchecked(
-                                            context_sentence.en.clone(),
-                                            context_sentence.ja.clone(),
-                                            vec![db::context_sentence::vocabulary_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .subject_meaning()
-                            .create_many(
-                                vocabulary_data
-                                    .meanings
-                                    .iter()
-                                    .map(|meaning| {
-                                        db::subject_meaning::create_unchecked(
-                                            meaning.accepted_answer,
-                                            meaning.meaning.clone(),
-                                            meaning.primary,
-                                            vec![db::subject_meaning::vocabulary_subject_id::set(
-                                                Some(subject.id as i32),
-                                            )],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                        client
-                            .vocabulary_reading()
-                            .create_many(
-                                vocabulary_data
-                                    .readings
-                                    .iter()
-                                    .map(|reading| {
-                                        db::vocabulary_reading::create_unchecked(
-                                            reading.reading.clone(),
-                                            reading.accepted_answer,
-                                            reading.primary,
-                                            vec![
-                                                db::vocabulary_reading::vocabulary_subject_id::set(
-                                                    Some(subject.id as i32),
-                                                ),
-                                            ],
-                                        )
-                                    })
-                                    .collect_vec(),
-                            )
-                            .exec()
-                            .await
-                            .unwrap();
-                    }
-                };
-            })
+                        })
+                        .collect_vec(),
+                )
+                .exec()
+                .instrument(batch_span("vocabulary_reading", "create_many", subject.id))
+                .await?;
+        }
+        SubjectData::KanaVocabulary(_) => {}
+    };
+
+    // Media hangs off the subject index, which is updated in place rather than recreated
+    client
+        .subject_media()
+        .delete_many(vec![db::subject_media::subject_id::equals(
+            subject.id as i32,
+        )])
+        .exec()
+        .instrument(batch_span("subject_media", "delete_many", subject.id))
+        .await?;
+    client
+        .subject_media()
+        .create_many(
+            subject_media(&subject.data)
+                .into_iter()
+                .map(|media| {
+                    db::subject_media::create_unchecked(
+                        subject.id as i32,
+                        match media.kind {
+                            MediaKind::CharacterImage => db::MediaKind::CharacterImage,
+                            MediaKind::PronunciationAudio => db::MediaKind::PronunciationAudio,
+                        },
+                        media.url,
+                        media.content_type,
+                        vec![db::subject_media::metadata::set(media.metadata)],
+                    )
+                })
+                .collect_vec(),
+        )
+        .exec()
+        .instrument(batch_span("subject_media", "create_many", subject.id))
+        .await?;
+    Ok(())
+}
+
+pub async fn upload_to_db(
+    map: HashMap<u32, SubjectDataOuter>,
+    database_url: Option<String>,
+    concurrency: usize,
+    skip_schema_check: bool,
+) -> Result<UploadReport, Box<dyn Error>> {
+    let client = Arc::new(connect(database_url, skip_schema_check).await?);
+    let subjects = map
+        .into_values()
+        .filter(|subject| !matches!(subject.data, SubjectData::KanaVocabulary(_)))
+        .collect_vec();
+    let subject_count = subjects.len();
+    info!(subjects = subject_count, concurrency, "uploading subjects");
+    let mut tasks = stream::iter(subjects)
+        .map(|subject| {
+            let client = client.clone();
+            let span = info_span!("upload_subject", subject_id = subject.id);
+            tokio::spawn(
+                async move { (subject.id, upload_subject(&client, &subject).await) }
+                    .instrument(span),
+            )
         })
-        .buffer_unordered(300);
+        .buffer_unordered(concurrency);
 
-    let progress_bar = ProgressBar::new(map.len() as u64);
+    let progress_bar = progress_bar(subject_count as u64);
+    let mut report = UploadReport::default();
 
     while let Some(res) = tasks.next().await {
-        res.unwrap();
+        match res? {
+            (_, Ok(())) => report.uploaded += 1,
+            (subject_id, Err(e)) => {
+                error!(subject_id, error = %e, "failed to upload subject");
+                report.failed += 1;
+            }
+        }
         progress_bar.inc(1);
     }
     progress_bar.finish();
 
-    Ok(())
+    Ok(report)
 }
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use itertools::Itertools;
+
+use japtools_core::{
+    mnemonic::parse_markup,
+    schema::{SubjectData, SubjectDataOuter, SubjectMeaning},
+};
+
+use crate::loader::MAX_LEVEL;
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub subject_id: u32,
+    pub message: String,
+}
+
+fn check_meanings(problems: &mut Vec<Problem>, subject_id: u32, meanings: &[SubjectMeaning]) {
+    let primary = meanings.iter().filter(|x| x.primary).count();
+    if primary != 1 {
+        problems.push(Problem {
+            subject_id,
+            message: format!("has {} primary meanings", primary),
+        });
+    }
+}
+
+fn check_markup(problems: &mut Vec<Problem>, subject_id: u32, field: &str, text: &str) {
+    if let Err(e) = parse_markup(text) {
+        problems.push(Problem {
+            subject_id,
+            message: format!("{}: {}", field, e),
+        });
+    }
+}
+
+fn check_references(
+    problems: &mut Vec<Problem>,
+    map: &HashMap<u32, SubjectDataOuter>,
+    subject_id: u32,
+    field: &str,
+    ids: &[u32],
+) {
+    for id in ids {
+        if !map.contains_key(id) {
+            problems.push(Problem {
+                subject_id,
+                message: format!("{} references missing subject {}", field, id),
+            });
+        }
+    }
+}
+
+// References are resolved against the whole map, only subjects within levels are checked
+pub fn validate(
+    map: &HashMap<u32, SubjectDataOuter>,
+    levels: &RangeInclusive<u32>,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for subject in map.values().sorted_by_key(|x| x.id) {
+        if let Some(level) = subject.data.level() {
+            if !(1..=MAX_LEVEL).contains(&level) {
+                problems.push(Problem {
+                    subject_id: subject.id,
+                    message: format!("level {} is out of range", level),
+                });
+            }
+            if !levels.contains(&level) {
+                continue;
+            }
+        }
+        match &subject.data {
+            SubjectData::Radical(data) => {
+                check_meanings(&mut problems, subject.id, &data.meanings);
+                check_markup(
+                    &mut problems,
+                    subject.id,
+                    "meaning_mnemonic",
+                    &data.meaning_mnemonic,
+                );
+                check_references(
+                    &mut problems,
+                    map,
+                    subject.id,
+                    "amalgamation_subject_ids",
+                    &data.amalgamation_subject_ids,
+                );
+                if data.characters.is_none() && data.character_images.is_empty() {
+                    problems.push(Problem {
+                        subject_id: subject.id,
+                        message: "has neither characters nor character images".to_string(),
+                    });
+                }
+            }
+            SubjectData::Kanji(data) => {
+                check_meanings(&mut problems, subject.id, &data.meanings);
+                for (field, text) in [
+                    ("meaning_mnemonic", Some(&data.meaning_mnemonic)),
+                    ("meaning_hint", data.meaning_hint.as_ref()),
+                    ("reading_mnemonic", Some(&data.reading_mnemonic)),
+                    ("reading_hint", Some(&data.reading_hint)),
+                ] {
+                    if let Some(text) = text {
+                        check_markup(&mut problems, subject.id, field, text);
+                    }
+                }
+                check_references(
+                    &mut problems,
+                    map,
+                    subject.id,
+                    "amalgamation_subject_ids",
+                    &data.amalgamation_subject_ids,
+                );
+                check_references(
+                    &mut problems,
+                    map,
+                    subject.id,
+                    "component_subject_ids",
+                    &data.component_subject_ids,
+                );
+                check_references(
+                    &mut problems,
+                    map,
+                    subject.id,
+                    "visually_similar_subject_ids",
+                    &data.visually_similar_subject_ids,
+                );
+                if !data.readings.iter().any(|x| x.primary) {
+                    problems.push(Problem {
+                        subject_id: subject.id,
+                        message: "has no primary reading".to_string(),
+                    });
+                }
+            }
+            SubjectData::Vocabulary(data) => {
+                check_meanings(&mut problems, subject.id, &data.meanings);
+                for (field, text) in [
+                    ("meaning_mnemonic", &data.meaning_mnemonic),
+                    ("reading_mnemonic", &data.reading_mnemonic),
+                ] {
+                    check_markup(&mut problems, subject.id, field, text);
+                }
+                check_references(
+                    &mut problems,
+                    map,
+                    subject.id,
+                    "component_subject_ids",
+                    &data.component_subject_ids,
+                );
+                if !data.readings.iter().any(|x| x.primary) {
+                    problems.push(Problem {
+                        subject_id: subject.id,
+                        message: "has no primary reading".to_string(),
+                    });
+                }
+            }
+            SubjectData::KanaVocabulary(_) => {}
+        }
+    }
+
+    // KanjiSubject.characters is unique in the database
+    let duplicate_kanji = map
+        .values()
+        .filter_map(|subject| match &subject.data {
+            SubjectData::Kanji(data) => Some((data.characters.as_str(), subject.id)),
+            _ => None,
+        })
+        .into_group_map();
+    for (characters, ids) in duplicate_kanji.into_iter().sorted() {
+        if ids.len() > 1 {
+            for id in ids.iter().sorted() {
+                problems.push(Problem {
+                    subject_id: *id,
+                    message: format!("kanji {} is not unique", characters),
+                });
+            }
+        }
+    }
+
+    problems
+}
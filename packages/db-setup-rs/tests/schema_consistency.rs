@@ -0,0 +1,254 @@
+use std::{collections::HashMap, fs, path::Path};
+
+// schema.rs struct -> Prisma model it is uploaded into
+const PERSISTED_STRUCTS: &[(&str, &str)] = &[
+    ("RadicalData", "RadicalSubject"),
+    ("KanjiData", "KanjiSubject"),
+    ("VocabularyData", "VocabularySubject"),
+    ("SubjectMeaning", "SubjectMeaning"),
+    ("AuxiliaryMeaning", "AuxiliaryMeaning"),
+    ("KanjiReading", "KanjiReading"),
+    ("VocabularyReading", "VocabularyReading"),
+    ("ContextSentence", "ContextSentence"),
+    ("ExampleSentence", "ExampleSentence"),
+];
+
+// (struct, field, column) where upload.rs stores a field under another name
+const RENAMED_FIELDS: &[(&str, &str, &str)] = &[
+    ("AuxiliaryMeaning", "meaning_type", "auxiliary_type"),
+    ("RadicalData", "character_images", "image_url"),
+];
+
+// WaniKani fields that are deliberately dropped by upload.rs
+const NOT_PERSISTED: &[(&str, &str)] = &[
+    ("RadicalData", "created_at"),
+    ("RadicalData", "document_url"),
+    ("RadicalData", "hidden_at"),
+    ("RadicalData", "slug"),
+    ("RadicalData", "spaced_repetition_system_id"),
+    ("KanjiData", "created_at"),
+    ("KanjiData", "document_url"),
+    ("KanjiData", "hidden_at"),
+    ("KanjiData", "slug"),
+    ("KanjiData", "spaced_repetition_system_id"),
+    ("VocabularyData", "created_at"),
+    ("VocabularyData", "document_url"),
+    ("VocabularyData", "hidden_at"),
+    ("VocabularyData", "slug"),
+    ("VocabularyData", "spaced_repetition_system_id"),
+    ("VocabularyData", "parts_of_speech"),
+    ("KanjiReading", "accepted_answer"),
+];
+
+// (struct, field, model) for lists stored as rows of a shared model instead of a column
+const SEPARATE_MODELS: &[(&str, &str, &str)] =
+    &[("VocabularyData", "pronunciation_audios", "SubjectMedia")];
+
+// Fields whose Rust type differs from the column because upload.rs converts them
+const CONVERTED_FIELDS: &[(&str, &str)] = &[
+    ("RadicalData", "character_images"),
+    ("VocabularyReading", "furigana"),
+    ("ContextSentence", "tokens"),
+    ("ExampleSentence", "tokens"),
+];
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: String,
+}
+
+fn block_bodies<'a>(source: &'a str, keyword: &str) -> HashMap<String, Vec<&'a str>> {
+    let mut blocks = HashMap::new();
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        let header = line.split_whitespace().collect::<Vec<_>>();
+        if let [kind, name, "{"] = header.as_slice() {
+            if *kind == keyword {
+                let body = lines
+                    .by_ref()
+                    .take_while(|x| x.trim() != "}")
+                    .collect::<Vec<_>>();
+                blocks.insert(name.to_string(), body);
+            }
+        }
+    }
+    blocks
+}
+
+fn rust_structs(source: &str) -> HashMap<String, Vec<Field>> {
+    let source = source.replace("pub struct ", "struct ");
+    block_bodies(&source, "struct")
+        .into_iter()
+        .map(|(name, body)| {
+            let fields = body
+                .iter()
+                .filter_map(|line| {
+                    let (name, ty) = line.trim().strip_prefix("pub ")?.split_once(':')?;
+                    Some(Field {
+                        name: name.trim().to_string(),
+                        ty: ty.trim().trim_end_matches(',').replace(' ', ""),
+                    })
+                })
+                .collect();
+            (name, fields)
+        })
+        .collect()
+}
+
+fn prisma_models(source: &str) -> HashMap<String, Vec<Field>> {
+    block_bodies(source, "model")
+        .into_iter()
+        .map(|(name, body)| {
+            let fields = body
+                .iter()
+                .filter_map(|line| {
+                    let mut parts = line.split_whitespace();
+                    let name = parts.next()?;
+                    if name.starts_with("//") || name.starts_with("@@") {
+                        return None;
+                    }
+                    Some(Field {
+                        name: name.to_string(),
+                        ty: parts.next()?.to_string(),
+                    })
+                })
+                .collect();
+            (name, fields)
+        })
+        .collect()
+}
+
+// What a persisted Rust field type is expected to look like in Prisma
+fn expected_prisma_type(rust_type: &str) -> Option<String> {
+    if let Some(inner) = rust_type
+        .strip_prefix("Option<")
+        .and_then(|x| x.strip_suffix('>'))
+    {
+        return expected_prisma_type(inner).map(|x| format!("{}?", x));
+    }
+    if let Some(inner) = rust_type
+        .strip_prefix("Vec<")
+        .and_then(|x| x.strip_suffix('>'))
+    {
+        return expected_prisma_type(inner).map(|x| format!("{}[]", x));
+    }
+    let scalar = match rust_type {
+        "u32" | "i32" => "Int",
+        "String" => "String",
+        "bool" => "Boolean",
+        "ReadingType" => "ReadingType",
+        "ReadingComposition" => "ReadingComposition",
+        _ => PERSISTED_STRUCTS
+            .iter()
+            .find(|(rust, _)| *rust == rust_type)
+            .map(|(_, model)| *model)?,
+    };
+    Some(scalar.to_string())
+}
+
+fn is_listed(list: &[(&str, &str)], structure: &str, field: &str) -> bool {
+    list.iter().any(|(s, f)| *s == structure && *f == field)
+}
+
+#[test]
+fn schema_rs_matches_prisma_models() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let structs =
+        rust_structs(&fs::read_to_string(root.join("../japtools-core/src/schema.rs")).unwrap());
+    let models =
+        prisma_models(&fs::read_to_string(root.join("../../prisma/schema.prisma")).unwrap());
+
+    let mut problems = Vec::new();
+    for (structure, model) in PERSISTED_STRUCTS {
+        let fields = structs
+            .get(*structure)
+            .unwrap_or_else(|| panic!("struct {} not found in schema.rs", structure));
+        let columns = models
+            .get(*model)
+            .unwrap_or_else(|| panic!("model {} not found in schema.prisma", model));
+
+        for field in fields {
+            if let Some((_, _, model)) = SEPARATE_MODELS
+                .iter()
+                .find(|(s, f, _)| s == structure && *f == field.name)
+            {
+                if !models.contains_key(*model) {
+                    problems.push(format!(
+                        "{}.{} is stored in {} but that model does not exist",
+                        structure, field.name, model
+                    ));
+                }
+                continue;
+            }
+            if is_listed(NOT_PERSISTED, structure, &field.name) {
+                if columns.iter().any(|x| x.name == field.name) {
+                    problems.push(format!(
+                        "{}.{} is listed as not persisted but {}.{} exists",
+                        structure, field.name, model, field.name
+                    ));
+                }
+                continue;
+            }
+            let column_name = RENAMED_FIELDS
+                .iter()
+                .find(|(s, f, _)| s == structure && *f == field.name)
+                .map(|(_, _, column)| *column)
+                .unwrap_or(&field.name);
+            let column = match columns.iter().find(|x| x.name == column_name) {
+                Some(column) => column,
+                None => {
+                    problems.push(format!(
+                        "{}.{} has no column {}.{}",
+                        structure, field.name, model, column_name
+                    ));
+                    continue;
+                }
+            };
+            if is_listed(CONVERTED_FIELDS, structure, &field.name) {
+                continue;
+            }
+            match expected_prisma_type(&field.ty) {
+                Some(expected) if expected == column.ty => {}
+                Some(expected) => problems.push(format!(
+                    "{}.{}: {} should be {} but {}.{} is {}",
+                    structure, field.name, field.ty, expected, model, column.name, column.ty
+                )),
+                None => problems.push(format!(
+                    "{}.{}: no Prisma type known for {}",
+                    structure, field.name, field.ty
+                )),
+            }
+        }
+    }
+
+    problems.sort();
+    assert!(
+        problems.is_empty(),
+        "schema.rs and schema.prisma disagree:\n  {}",
+        problems.join("\n  ")
+    );
+}
+
+#[test]
+fn not_persisted_fields_exist() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let structs =
+        rust_structs(&fs::read_to_string(root.join("../japtools-core/src/schema.rs")).unwrap());
+    let separate = SEPARATE_MODELS
+        .iter()
+        .map(|(s, f, _)| (*s, *f))
+        .collect::<Vec<_>>();
+    for (structure, field) in NOT_PERSISTED
+        .iter()
+        .chain(CONVERTED_FIELDS)
+        .chain(&separate)
+    {
+        assert!(
+            structs[*structure].iter().any(|x| x.name == *field),
+            "{}.{} is listed but no longer exists in schema.rs",
+            structure,
+            field
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    kana::{is_hiragana, is_katakana, to_hiragana},
+    schema::{KanjiReading, ReadingType},
+};
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SegmentKind {
+    Onyomi,
+    Kunyomi,
+    Nanori,
+    // Kanji whose reading is not built from their own readings, like 今日 or 大人
+    Irregular,
+    // Okurigana and other kana written out in the vocabulary itself
+    None,
+}
+
+impl From<&ReadingType> for SegmentKind {
+    fn from(reading_type: &ReadingType) -> Self {
+        match reading_type {
+            ReadingType::Onyomi => SegmentKind::Onyomi,
+            ReadingType::Kunyomi => SegmentKind::Kunyomi,
+            ReadingType::Nanori => SegmentKind::Nanori,
+        }
+    }
+}
+
+// How a vocabulary reading relates to the readings of its kanji
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingComposition {
+    // Plain on'yomi and kun'yomi put together: 学生
+    Regular,
+    // Needs rendaku or a small っ on top of that: 手紙, 学校
+    SoundChange,
+    // At least one span only reads this way as a whole, jukujikun and ateji: 今日, 大人
+    Jukujikun,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FuriganaSegment {
+    pub text: String,
+    pub reading: String,
+    pub kind: SegmentKind,
+}
+
+const ITERATION_MARK: char = '々';
+
+// Voiced forms a kana can take when it starts a later part of a compound
+const RENDAKU: &[(char, &str)] = &[
+    ('か', "が"),
+    ('き', "ぎ"),
+    ('く', "ぐ"),
+    ('け', "げ"),
+    ('こ', "ご"),
+    ('さ', "ざ"),
+    ('し', "じ"),
+    ('す', "ず"),
+    ('せ', "ぜ"),
+    ('そ', "ぞ"),
+    ('た', "だ"),
+    ('ち', "ぢじ"),
+    ('つ', "づず"),
+    ('て', "で"),
+    ('と', "ど"),
+    ('は', "ばぱ"),
+    ('ひ', "びぴ"),
+    ('ふ', "ぶぷ"),
+    ('へ', "べぺ"),
+    ('ほ', "ぼぽ"),
+];
+
+// Kana that turn into っ before the next part of a compound: がく -> がっこう
+const GEMINATING: &[char] = &['つ', 'ち', 'く', 'き'];
+
+enum Unit {
+    Kanji(char),
+    Kana(String),
+}
+
+fn units(characters: &str) -> Vec<Unit> {
+    let mut units: Vec<Unit> = Vec::new();
+    for c in characters.chars() {
+        if is_hiragana(c) || is_katakana(c) || c == 'ー' {
+            if let Some(Unit::Kana(kana)) = units.last_mut() {
+                kana.push(c);
+                continue;
+            }
+            units.push(Unit::Kana(c.to_string()));
+        } else {
+            units.push(Unit::Kanji(c));
+        }
+    }
+    units
+}
+
+// Every form a kanji reading can take inside a word, with how many sound changes it needed
+fn variants(reading: &str, first: bool, last: bool) -> Vec<(String, u32)> {
+    let mut starts = vec![(reading.to_string(), 0)];
+    let mut chars = reading.chars();
+    if let (false, Some(head)) = (first, chars.next()) {
+        let rest = chars.as_str();
+        if let Some((_, voiced)) = RENDAKU.iter().find(|(c, _)| *c == head) {
+            starts.extend(voiced.chars().map(|v| (format!("{}{}", v, rest), 1)));
+        }
+    }
+    let mut variants = starts.clone();
+    if !last {
+        for (start, changes) in starts {
+            if let Some(stem) = start.strip_suffix(GEMINATING) {
+                variants.push((format!("{}っ", stem), changes + 1));
+            }
+        }
+    }
+    variants
+}
+
+// Lower is better: irregular segments first, then sound changes, then fewer segments
+type Cost = (u32, u32, u32);
+type Alignment = Option<(Cost, Vec<FuriganaSegment>)>;
+
+struct Aligner<'a> {
+    units: Vec<Unit>,
+    reading: Vec<char>,
+    readings: &'a HashMap<char, Vec<KanjiReading>>,
+    memo: HashMap<(usize, usize), Alignment>,
+}
+
+impl<'a> Aligner<'a> {
+    fn kanji_readings(&self, unit: usize) -> &'a [KanjiReading] {
+        // 々 repeats the kanji before it
+        let kanji = (0..=unit).rev().find_map(|i| match self.units[i] {
+            Unit::Kanji(c) if c != ITERATION_MARK => Some(c),
+            _ => None,
+        });
+        kanji
+            .and_then(|c| self.readings.get(&c))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn rest(&self, position: usize) -> String {
+        self.reading[position..].iter().collect()
+    }
+
+    fn best(&mut self, unit: usize, position: usize) -> Alignment {
+        if let Some(result) = self.memo.get(&(unit, position)) {
+            return result.clone();
+        }
+        let result = self.solve(unit, position);
+        self.memo.insert((unit, position), result.clone());
+        result
+    }
+
+    fn solve(&mut self, unit: usize, position: usize) -> Alignment {
+        if unit == self.units.len() {
+            return (position == self.reading.len()).then_some(((0, 0, 0), Vec::new()));
+        }
+        let mut candidates: Vec<(Cost, FuriganaSegment, usize, usize)> = Vec::new();
+        match &self.units[unit] {
+            Unit::Kana(kana) => {
+                let reading = to_hiragana(kana);
+                if self.rest(position).starts_with(&reading) {
+                    candidates.push((
+                        (0, 0, 1),
+                        FuriganaSegment {
+                            text: kana.clone(),
+                            reading: reading.clone(),
+                            kind: SegmentKind::None,
+                        },
+                        unit + 1,
+                        position + reading.chars().count(),
+                    ));
+                }
+            }
+            Unit::Kanji(c) => {
+                let rest = self.rest(position);
+                let last = unit + 1 == self.units.len();
+                for reading in self.kanji_readings(unit) {
+                    let base = to_hiragana(&reading.reading);
+                    for (variant, changes) in variants(&base, unit == 0, last) {
+                        if !variant.is_empty() && rest.starts_with(&variant) {
+                            candidates.push((
+                                (0, changes, 1),
+                                FuriganaSegment {
+                                    text: c.to_string(),
+                                    reading: variant.clone(),
+                                    kind: (&reading.reading_type).into(),
+                                },
+                                unit + 1,
+                                position + variant.chars().count(),
+                            ));
+                        }
+                    }
+                }
+                // Any run of kanji may also share a reading as a whole
+                let run_end = (unit..self.units.len())
+                    .find(|i| matches!(self.units[*i], Unit::Kana(_)))
+                    .unwrap_or(self.units.len());
+                for end in unit + 1..=run_end {
+                    let text = self.units[unit..end]
+                        .iter()
+                        .filter_map(|x| match x {
+                            Unit::Kanji(c) => Some(*c),
+                            Unit::Kana(_) => None,
+                        })
+                        .collect::<String>();
+                    for length in 1..=self.reading.len() - position {
+                        candidates.push((
+                            (1, 0, 1),
+                            FuriganaSegment {
+                                text: text.clone(),
+                                reading: self.reading[position..position + length].iter().collect(),
+                                kind: SegmentKind::Irregular,
+                            },
+                            end,
+                            position + length,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut best: Alignment = None;
+        for (cost, segment, next_unit, next_position) in candidates {
+            if let Some((rest_cost, rest)) = self.best(next_unit, next_position) {
+                let total = (
+                    cost.0 + rest_cost.0,
+                    cost.1 + rest_cost.1,
+                    cost.2 + rest_cost.2,
+                );
+                if !matches!(&best, Some((best, _)) if *best <= total) {
+                    let mut segments = vec![segment];
+                    segments.extend(rest);
+                    best = Some((total, segments));
+                }
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadingAnalysis {
+    pub furigana: Vec<FuriganaSegment>,
+    pub composition: ReadingComposition,
+}
+
+// Splits a vocabulary reading over its characters, preferring the kanji's own readings
+// and falling back to irregular segments for spans that cannot be built from them
+pub fn analyze_reading(
+    characters: &str,
+    reading: &str,
+    readings: &HashMap<char, Vec<KanjiReading>>,
+) -> Option<ReadingAnalysis> {
+    let mut aligner = Aligner {
+        units: units(characters),
+        reading: to_hiragana(reading).chars().collect(),
+        readings,
+        memo: HashMap::new(),
+    };
+    let ((irregular, sound_changes, _), segments) = aligner.best(0, 0)?;
+    let composition = if irregular > 0 {
+        ReadingComposition::Jukujikun
+    } else if sound_changes > 0 {
+        ReadingComposition::SoundChange
+    } else {
+        ReadingComposition::Regular
+    };
+    Some(ReadingAnalysis {
+        furigana: merge_irregular(segments),
+        composition,
+    })
+}
+
+// Neighbouring irregular segments read as one word: 今日 rather than 今 + 日
+fn merge_irregular(segments: Vec<FuriganaSegment>) -> Vec<FuriganaSegment> {
+    let mut merged: Vec<FuriganaSegment> = Vec::new();
+    for segment in segments {
+        if let Some(last) = merged.last_mut() {
+            if last.kind == SegmentKind::Irregular && segment.kind == SegmentKind::Irregular {
+                last.text.push_str(&segment.text);
+                last.reading.push_str(&segment.reading);
+                continue;
+            }
+        }
+        merged.push(segment);
+    }
+    merged
+}
+
+// One entry per character, the shape the vocabulary_kun_on_yomi lesson expects
+pub fn string_map(segments: &[FuriganaSegment]) -> Vec<SegmentKind> {
+    segments
+        .iter()
+        .flat_map(|x| x.text.chars().map(move |_| x.kind))
+        .collect()
+}
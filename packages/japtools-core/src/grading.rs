@@ -0,0 +1,320 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ime::to_kana,
+    kana::{to_full_width, to_hiragana},
+    normalize::meaning_key,
+    schema::{
+        AuxiliaryMeaning, KanjiReading, ReadingType, SubjectData, SubjectMeaning, VocabularyReading,
+    },
+};
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerType {
+    Meaning,
+    Reading,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Correct,
+    // Within the typo tolerance, passes but the exact answer should be shown
+    Close,
+    // A reading of the subject that is not accepted as the answer
+    OtherReading,
+    // A kanji reading of another type than the one asked for, the learner should try again
+    WrongReadingType { expected: ReadingType },
+    Blacklisted,
+    Wrong,
+}
+
+impl Verdict {
+    pub fn is_correct(&self) -> bool {
+        matches!(self, Verdict::Correct | Verdict::Close)
+    }
+
+    pub fn should_retry(&self) -> bool {
+        matches!(self, Verdict::WrongReadingType { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leniency {
+    // Share of an accepted meaning's characters that may be mistyped, 0 only accepts exact answers
+    pub typo_ratio: f64,
+    // Meanings up to this many characters have to be typed exactly
+    pub exact_length: usize,
+    // Lets 3 match three and the other way around
+    pub number_words: bool,
+}
+
+// Close to the web app's similarity threshold of 0.8
+impl Default for Leniency {
+    fn default() -> Self {
+        Self {
+            typo_ratio: 0.2,
+            exact_length: 3,
+            number_words: true,
+        }
+    }
+}
+
+impl Leniency {
+    pub fn max_typos(&self, length: usize) -> usize {
+        if length <= self.exact_length {
+            0
+        } else {
+            (length as f64 * self.typo_ratio).floor() as usize
+        }
+    }
+}
+
+// Meanings like "to do something" may be answered without the trailing word
+const OPTIONAL_WORDS: &[&str] = &["something"];
+
+const ONES: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: &[&str] = &[
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: &[(u64, &str)] = &[
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+    (100, "hundred"),
+];
+
+pub fn number_to_words(number: u64) -> String {
+    if let Some((scale, name)) = SCALES.iter().find(|(scale, _)| number >= *scale) {
+        let rest = number % scale;
+        let head = format!("{} {}", number_to_words(number / scale), name);
+        return match rest {
+            0 => head,
+            _ => format!("{} {}", head, number_to_words(rest)),
+        };
+    }
+    let number = number as usize;
+    match (number / 10, number % 10) {
+        (0 | 1, _) => ONES[number].to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, ones) => format!("{} {}", TENS[tens], ONES[ones]),
+    }
+}
+
+fn meaning_forms(text: &str, leniency: &Leniency) -> Vec<String> {
+    let key = meaning_key(text);
+    let mut forms = vec![key.clone()];
+    if leniency.number_words {
+        let words = key
+            .split(' ')
+            .map(|word| match word.parse::<u64>() {
+                Ok(number) => number_to_words(number),
+                Err(_) => word.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        forms.push(words);
+    }
+    for form in forms.clone() {
+        for word in OPTIONAL_WORDS {
+            if let Some(stripped) = form.strip_suffix(&format!(" {}", word)) {
+                forms.push(stripped.to_string());
+            }
+        }
+    }
+    forms
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (previous + (x != *y) as usize)
+                .min(row[j] + 1)
+                .min(current + 1);
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+// Smallest edit distance between any form of the answer and any form of the meaning
+fn distance(answer: &[String], meaning: &str, leniency: &Leniency) -> (usize, usize) {
+    meaning_forms(meaning, leniency)
+        .iter()
+        .flat_map(|form| {
+            answer
+                .iter()
+                .map(move |x| (levenshtein(x, form), form.chars().count()))
+        })
+        .min()
+        .unwrap_or((usize::MAX, 0))
+}
+
+// Blacklisted meanings win over accepted ones they are at least as close to
+pub fn grade_meaning(
+    answer: &str,
+    meanings: &[SubjectMeaning],
+    auxiliary_meanings: &[AuxiliaryMeaning],
+    leniency: &Leniency,
+) -> Verdict {
+    let answer = meaning_forms(answer, leniency);
+    if answer.iter().all(|x| x.is_empty()) {
+        return Verdict::Wrong;
+    }
+    let accepted = meanings
+        .iter()
+        .filter(|x| x.accepted_answer)
+        .map(|x| x.meaning.as_str())
+        .chain(
+            auxiliary_meanings
+                .iter()
+                .filter(|x| x.meaning_type == "whitelist")
+                .map(|x| x.meaning.as_str()),
+        )
+        .map(|x| distance(&answer, x, leniency))
+        .min();
+    let blacklisted = auxiliary_meanings
+        .iter()
+        .filter(|x| x.meaning_type == "blacklist")
+        .map(|x| distance(&answer, &x.meaning, leniency).0)
+        .min();
+
+    match (accepted, blacklisted) {
+        (Some((0, _)), _) => Verdict::Correct,
+        (_, Some(0)) => Verdict::Blacklisted,
+        (Some((typos, length)), blacklisted) if typos <= leniency.max_typos(length) => {
+            match blacklisted {
+                Some(blacklisted) if blacklisted <= typos => Verdict::Blacklisted,
+                _ => Verdict::Close,
+            }
+        }
+        _ => Verdict::Wrong,
+    }
+}
+
+// Readings may be typed in romaji, katakana or half-width katakana
+pub fn reading_key(text: &str) -> String {
+    to_hiragana(&to_full_width(&to_kana(text.trim(), false)))
+}
+
+fn grade_readings<'a>(
+    answer: &str,
+    readings: impl IntoIterator<Item = (&'a str, bool)>,
+) -> Verdict {
+    let answer = reading_key(answer);
+    let mut verdict = Verdict::Wrong;
+    for (reading, accepted) in readings {
+        if reading_key(reading) == answer {
+            if accepted {
+                return Verdict::Correct;
+            }
+            verdict = Verdict::OtherReading;
+        }
+    }
+    verdict
+}
+
+// The primary reading decides which type is asked for, usually onyomi
+pub fn grade_kanji_reading(answer: &str, readings: &[KanjiReading]) -> Verdict {
+    let verdict = grade_readings(
+        answer,
+        readings
+            .iter()
+            .map(|x| (x.reading.as_str(), x.accepted_answer)),
+    );
+    let expected = match readings.iter().find(|x| x.primary) {
+        Some(primary) if verdict == Verdict::OtherReading => primary.reading_type,
+        _ => return verdict,
+    };
+    let answer = reading_key(answer);
+    let other_type = readings
+        .iter()
+        .filter(|x| reading_key(&x.reading) == answer)
+        .all(|x| x.reading_type != expected);
+    if other_type {
+        Verdict::WrongReadingType { expected }
+    } else {
+        verdict
+    }
+}
+
+pub fn grade_vocabulary_reading(answer: &str, readings: &[VocabularyReading]) -> Verdict {
+    grade_readings(
+        answer,
+        readings
+            .iter()
+            .map(|x| (x.reading.as_str(), x.accepted_answer)),
+    )
+}
+
+pub fn grade(
+    subject: &SubjectData,
+    answer_type: AnswerType,
+    answer: &str,
+    leniency: &Leniency,
+) -> Verdict {
+    match (subject, answer_type) {
+        (SubjectData::Radical(data), AnswerType::Meaning) => {
+            grade_meaning(answer, &data.meanings, &data.auxiliary_meanings, leniency)
+        }
+        (SubjectData::Kanji(data), AnswerType::Meaning) => {
+            grade_meaning(answer, &data.meanings, &data.auxiliary_meanings, leniency)
+        }
+        (SubjectData::Vocabulary(data), AnswerType::Meaning) => {
+            grade_meaning(answer, &data.meanings, &data.auxiliary_meanings, leniency)
+        }
+        (SubjectData::Kanji(data), AnswerType::Reading) => {
+            grade_kanji_reading(answer, &data.readings)
+        }
+        (SubjectData::Vocabulary(data), AnswerType::Reading) => {
+            grade_vocabulary_reading(answer, &data.readings)
+        }
+        // Kana vocabulary keeps the raw payload, its characters are the reading
+        (SubjectData::KanaVocabulary(data), AnswerType::Meaning) => {
+            let field = |name: &str| data.get(name).cloned().unwrap_or_default();
+            grade_meaning(
+                answer,
+                &serde_json::from_value::<Vec<SubjectMeaning>>(field("meanings"))
+                    .unwrap_or_default(),
+                &serde_json::from_value::<Vec<AuxiliaryMeaning>>(field("auxiliary_meanings"))
+                    .unwrap_or_default(),
+                leniency,
+            )
+        }
+        (SubjectData::KanaVocabulary(data), AnswerType::Reading) => grade_readings(
+            answer,
+            data.get("characters")
+                .and_then(|x| x.as_str())
+                .map(|x| (x, true)),
+        ),
+        (SubjectData::Radical(_), AnswerType::Reading) => Verdict::Wrong,
+    }
+}
@@ -0,0 +1,376 @@
+use crate::kana::to_katakana;
+
+// Typed romaji and the kana it turns into, following WanaKana's mapping. Spellings that
+// are a prefix of a longer one (n, sh, ts, xt) wait for the next key before converting
+const INPUT_TABLE: &[(&str, &str)] = &[
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("kya", "きゃ"),
+    ("kyi", "きぃ"),
+    ("kyu", "きゅ"),
+    ("kye", "きぇ"),
+    ("kyo", "きょ"),
+    ("kwa", "くぁ"),
+    ("ca", "か"),
+    ("ci", "し"),
+    ("cu", "く"),
+    ("ce", "せ"),
+    ("co", "こ"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("gya", "ぎゃ"),
+    ("gyi", "ぎぃ"),
+    ("gyu", "ぎゅ"),
+    ("gye", "ぎぇ"),
+    ("gyo", "ぎょ"),
+    ("gwa", "ぐぁ"),
+    ("sa", "さ"),
+    ("si", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("shi", "し"),
+    ("sha", "しゃ"),
+    ("shu", "しゅ"),
+    ("she", "しぇ"),
+    ("sho", "しょ"),
+    ("sya", "しゃ"),
+    ("syi", "しぃ"),
+    ("syu", "しゅ"),
+    ("sye", "しぇ"),
+    ("syo", "しょ"),
+    ("za", "ざ"),
+    ("zi", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("zya", "じゃ"),
+    ("zyi", "じぃ"),
+    ("zyu", "じゅ"),
+    ("zye", "じぇ"),
+    ("zyo", "じょ"),
+    ("ja", "じゃ"),
+    ("ji", "じ"),
+    ("ju", "じゅ"),
+    ("je", "じぇ"),
+    ("jo", "じょ"),
+    ("jya", "じゃ"),
+    ("jyi", "じぃ"),
+    ("jyu", "じゅ"),
+    ("jye", "じぇ"),
+    ("jyo", "じょ"),
+    ("ta", "た"),
+    ("ti", "ち"),
+    ("tu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("chi", "ち"),
+    ("cha", "ちゃ"),
+    ("chu", "ちゅ"),
+    ("che", "ちぇ"),
+    ("cho", "ちょ"),
+    ("tya", "ちゃ"),
+    ("tyi", "ちぃ"),
+    ("tyu", "ちゅ"),
+    ("tye", "ちぇ"),
+    ("tyo", "ちょ"),
+    ("cya", "ちゃ"),
+    ("cyi", "ちぃ"),
+    ("cyu", "ちゅ"),
+    ("cye", "ちぇ"),
+    ("cyo", "ちょ"),
+    ("tsa", "つぁ"),
+    ("tsi", "つぃ"),
+    ("tsu", "つ"),
+    ("tse", "つぇ"),
+    ("tso", "つぉ"),
+    ("tha", "てゃ"),
+    ("thi", "てぃ"),
+    ("thu", "てゅ"),
+    ("the", "てぇ"),
+    ("tho", "てょ"),
+    ("twu", "とぅ"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("dya", "ぢゃ"),
+    ("dyi", "ぢぃ"),
+    ("dyu", "ぢゅ"),
+    ("dye", "ぢぇ"),
+    ("dyo", "ぢょ"),
+    ("dha", "でゃ"),
+    ("dhi", "でぃ"),
+    ("dhu", "でゅ"),
+    ("dhe", "でぇ"),
+    ("dho", "でょ"),
+    ("dwu", "どぅ"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("nya", "にゃ"),
+    ("nyi", "にぃ"),
+    ("nyu", "にゅ"),
+    ("nye", "にぇ"),
+    ("nyo", "にょ"),
+    ("n'", "ん"),
+    ("xn", "ん"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("hu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("hya", "ひゃ"),
+    ("hyi", "ひぃ"),
+    ("hyu", "ひゅ"),
+    ("hye", "ひぇ"),
+    ("hyo", "ひょ"),
+    ("fa", "ふぁ"),
+    ("fi", "ふぃ"),
+    ("fu", "ふ"),
+    ("fe", "ふぇ"),
+    ("fo", "ふぉ"),
+    ("fya", "ふゃ"),
+    ("fyu", "ふゅ"),
+    ("fyo", "ふょ"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("bya", "びゃ"),
+    ("byi", "びぃ"),
+    ("byu", "びゅ"),
+    ("bye", "びぇ"),
+    ("byo", "びょ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("pya", "ぴゃ"),
+    ("pyi", "ぴぃ"),
+    ("pyu", "ぴゅ"),
+    ("pye", "ぴぇ"),
+    ("pyo", "ぴょ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("mya", "みゃ"),
+    ("myi", "みぃ"),
+    ("myu", "みゅ"),
+    ("mye", "みぇ"),
+    ("myo", "みょ"),
+    ("ya", "や"),
+    ("yi", "い"),
+    ("yu", "ゆ"),
+    ("ye", "いぇ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("rya", "りゃ"),
+    ("ryi", "りぃ"),
+    ("ryu", "りゅ"),
+    ("rye", "りぇ"),
+    ("ryo", "りょ"),
+    ("wa", "わ"),
+    ("wi", "うぃ"),
+    ("we", "うぇ"),
+    ("wo", "を"),
+    ("va", "ゔぁ"),
+    ("vi", "ゔぃ"),
+    ("vu", "ゔ"),
+    ("ve", "ゔぇ"),
+    ("vo", "ゔぉ"),
+    ("vya", "ゔゃ"),
+    ("vyu", "ゔゅ"),
+    ("vyo", "ゔょ"),
+    // x and l type small kana on their own, ヵ and ヶ are the forms used in running text
+    ("xa", "ぁ"),
+    ("xi", "ぃ"),
+    ("xu", "ぅ"),
+    ("xe", "ぇ"),
+    ("xo", "ぉ"),
+    ("xya", "ゃ"),
+    ("xyu", "ゅ"),
+    ("xyo", "ょ"),
+    ("xtu", "っ"),
+    ("xtsu", "っ"),
+    ("xwa", "ゎ"),
+    ("xka", "ヵ"),
+    ("xke", "ヶ"),
+    ("la", "ぁ"),
+    ("li", "ぃ"),
+    ("lu", "ぅ"),
+    ("le", "ぇ"),
+    ("lo", "ぉ"),
+    ("lya", "ゃ"),
+    ("lyu", "ゅ"),
+    ("lyo", "ょ"),
+    ("ltu", "っ"),
+    ("ltsu", "っ"),
+    ("lwa", "ゎ"),
+    ("lka", "ヵ"),
+    ("lke", "ヶ"),
+    ("-", "ー"),
+    ("!", "！"),
+    ("?", "？"),
+    (".", "。"),
+    (",", "、"),
+    (":", "："),
+    ("/", "・"),
+    ("~", "〜"),
+    ("[", "［"),
+    ("]", "］"),
+    ("(", "（"),
+    (")", "）"),
+    ("{", "｛"),
+    ("}", "｝"),
+];
+
+// Only an IME takes nn as one ん, converting a whole string reads onna as おんな
+const IME_TABLE: &[(&str, &str)] = &[("nn", "ん"), ("n ", "ん")];
+
+fn table(ime_mode: bool) -> impl Iterator<Item = &'static (&'static str, &'static str)> {
+    let ime: &[(&str, &str)] = if ime_mode { IME_TABLE } else { &[] };
+    INPUT_TABLE.iter().chain(ime)
+}
+
+fn is_consonant(c: char) -> bool {
+    c.is_ascii_alphabetic() && !matches!(c.to_ascii_lowercase(), 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+fn lookup(romaji: &str, ime_mode: bool) -> Option<&'static str> {
+    table(ime_mode)
+        .find(|(key, _)| *key == romaji)
+        .map(|(_, kana)| *kana)
+}
+
+fn can_continue(romaji: &str, ime_mode: bool) -> bool {
+    table(ime_mode).any(|(key, _)| key.len() > romaji.len() && key.starts_with(romaji))
+}
+
+// Romaji typed in capitals comes out as katakana, like WanaKana does
+fn in_case(kana: &str, romaji: &str) -> String {
+    if romaji.chars().any(|c| c.is_ascii_uppercase())
+        && !romaji.chars().any(|c| c.is_ascii_lowercase())
+    {
+        to_katakana(kana)
+    } else {
+        kana.to_string()
+    }
+}
+
+// Converts keystrokes as they are typed. Romaji that may still become a different kana
+// stays pending and is shown as typed until the next key decides it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RomajiInput {
+    converted: String,
+    pending: String,
+    ime_mode: bool,
+}
+
+impl RomajiInput {
+    // Keystrokes are converted like an IME does
+    pub fn new() -> Self {
+        Self {
+            ime_mode: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.pending.push(c);
+        self.convert(false);
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.push(c);
+        }
+    }
+
+    // Removes a pending romaji letter first, then converted kana
+    pub fn backspace(&mut self) {
+        if self.pending.pop().is_none() {
+            self.converted.pop();
+        }
+    }
+
+    // Converts what is still pending, a final n becomes ん
+    pub fn finish(&mut self) {
+        self.convert(true);
+    }
+
+    pub fn text(&self) -> String {
+        format!("{}{}", self.converted, self.pending)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn convert(&mut self, finish: bool) {
+        while !self.pending.is_empty() {
+            let lower = self.pending.to_ascii_lowercase();
+            if !finish && can_continue(&lower, self.ime_mode) {
+                return;
+            }
+            if let Some(kana) = lookup(&lower, self.ime_mode) {
+                self.converted.push_str(&in_case(kana, &self.pending));
+                self.pending.clear();
+                return;
+            }
+            // Nothing starts with the pending romaji, so its first letter is decided on its own
+            let mut chars = self.pending.chars();
+            let first = chars.next().unwrap_or_default();
+            let second = chars.next();
+            let lower_first = first.to_ascii_lowercase();
+            let kana = if lower_first == 'n' {
+                in_case("ん", &first.to_string())
+            } else if is_consonant(first)
+                && (second.map(|c| c.to_ascii_lowercase()) == Some(lower_first)
+                    || (lower_first == 't' && matches!(second, Some('c' | 'C'))))
+            {
+                in_case("っ", &first.to_string())
+            } else {
+                first.to_string()
+            };
+            self.converted.push_str(&kana);
+            self.pending.drain(..first.len_utf8());
+        }
+    }
+}
+
+// Converts romaji in a whole string. In IME mode a trailing n or other unfinished romaji is
+// kept as typed, so converting the value of an input field again after each key is stable
+pub fn to_kana(text: &str, ime_mode: bool) -> String {
+    let mut input = RomajiInput {
+        ime_mode,
+        ..RomajiInput::default()
+    };
+    input.push_str(text);
+    if !ime_mode {
+        input.finish();
+    }
+    input.text()
+}
@@ -0,0 +1,453 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomajiSystem {
+    Hepburn,
+    Kunrei,
+    NihonShiki,
+}
+
+const KATAKANA_OFFSET: u32 = 0x60;
+
+// (kana, hepburn, kunrei, nihon-shiki), digraphs before the single kana they start with
+const ROMAJI_TABLE: &[(&str, &str, &str, &str)] = &[
+    ("きゃ", "kya", "kya", "kya"),
+    ("きゅ", "kyu", "kyu", "kyu"),
+    ("きょ", "kyo", "kyo", "kyo"),
+    ("ぎゃ", "gya", "gya", "gya"),
+    ("ぎゅ", "gyu", "gyu", "gyu"),
+    ("ぎょ", "gyo", "gyo", "gyo"),
+    ("しゃ", "sha", "sya", "sya"),
+    ("しゅ", "shu", "syu", "syu"),
+    ("しぇ", "she", "sye", "sye"),
+    ("しょ", "sho", "syo", "syo"),
+    ("じゃ", "ja", "zya", "zya"),
+    ("じゅ", "ju", "zyu", "zyu"),
+    ("じぇ", "je", "zye", "zye"),
+    ("じょ", "jo", "zyo", "zyo"),
+    ("ちゃ", "cha", "tya", "tya"),
+    ("ちゅ", "chu", "tyu", "tyu"),
+    ("ちぇ", "che", "tye", "tye"),
+    ("ちょ", "cho", "tyo", "tyo"),
+    ("ぢゃ", "ja", "zya", "dya"),
+    ("ぢゅ", "ju", "zyu", "dyu"),
+    ("ぢょ", "jo", "zyo", "dyo"),
+    ("にゃ", "nya", "nya", "nya"),
+    ("にゅ", "nyu", "nyu", "nyu"),
+    ("にょ", "nyo", "nyo", "nyo"),
+    ("ひゃ", "hya", "hya", "hya"),
+    ("ひゅ", "hyu", "hyu", "hyu"),
+    ("ひょ", "hyo", "hyo", "hyo"),
+    ("びゃ", "bya", "bya", "bya"),
+    ("びゅ", "byu", "byu", "byu"),
+    ("びょ", "byo", "byo", "byo"),
+    ("ぴゃ", "pya", "pya", "pya"),
+    ("ぴゅ", "pyu", "pyu", "pyu"),
+    ("ぴょ", "pyo", "pyo", "pyo"),
+    ("みゃ", "mya", "mya", "mya"),
+    ("みゅ", "myu", "myu", "myu"),
+    ("みょ", "myo", "myo", "myo"),
+    ("りゃ", "rya", "rya", "rya"),
+    ("りゅ", "ryu", "ryu", "ryu"),
+    ("りょ", "ryo", "ryo", "ryo"),
+    ("ふぁ", "fa", "fa", "fa"),
+    ("ふぃ", "fi", "fi", "fi"),
+    ("ふぇ", "fe", "fe", "fe"),
+    ("ふぉ", "fo", "fo", "fo"),
+    ("てぃ", "ti", "ti", "ti"),
+    ("でぃ", "di", "di", "di"),
+    ("とぅ", "tu", "tu", "tu"),
+    ("どぅ", "du", "du", "du"),
+    ("うぃ", "wi", "wi", "wi"),
+    ("うぇ", "we", "we", "we"),
+    ("うぉ", "wo", "wo", "wo"),
+    ("ゔぁ", "va", "va", "va"),
+    ("ゔぃ", "vi", "vi", "vi"),
+    ("ゔぇ", "ve", "ve", "ve"),
+    ("ゔぉ", "vo", "vo", "vo"),
+    ("あ", "a", "a", "a"),
+    ("い", "i", "i", "i"),
+    ("う", "u", "u", "u"),
+    ("え", "e", "e", "e"),
+    ("お", "o", "o", "o"),
+    ("か", "ka", "ka", "ka"),
+    ("き", "ki", "ki", "ki"),
+    ("く", "ku", "ku", "ku"),
+    ("け", "ke", "ke", "ke"),
+    ("こ", "ko", "ko", "ko"),
+    ("が", "ga", "ga", "ga"),
+    ("ぎ", "gi", "gi", "gi"),
+    ("ぐ", "gu", "gu", "gu"),
+    ("げ", "ge", "ge", "ge"),
+    ("ご", "go", "go", "go"),
+    ("さ", "sa", "sa", "sa"),
+    ("し", "shi", "si", "si"),
+    ("す", "su", "su", "su"),
+    ("せ", "se", "se", "se"),
+    ("そ", "so", "so", "so"),
+    ("ざ", "za", "za", "za"),
+    ("じ", "ji", "zi", "zi"),
+    ("ず", "zu", "zu", "zu"),
+    ("ぜ", "ze", "ze", "ze"),
+    ("ぞ", "zo", "zo", "zo"),
+    ("た", "ta", "ta", "ta"),
+    ("ち", "chi", "ti", "ti"),
+    ("つ", "tsu", "tu", "tu"),
+    ("て", "te", "te", "te"),
+    ("と", "to", "to", "to"),
+    ("だ", "da", "da", "da"),
+    ("ぢ", "ji", "zi", "di"),
+    ("づ", "zu", "zu", "du"),
+    ("で", "de", "de", "de"),
+    ("ど", "do", "do", "do"),
+    ("な", "na", "na", "na"),
+    ("に", "ni", "ni", "ni"),
+    ("ぬ", "nu", "nu", "nu"),
+    ("ね", "ne", "ne", "ne"),
+    ("の", "no", "no", "no"),
+    ("は", "ha", "ha", "ha"),
+    ("ひ", "hi", "hi", "hi"),
+    ("ふ", "fu", "hu", "hu"),
+    ("へ", "he", "he", "he"),
+    ("ほ", "ho", "ho", "ho"),
+    ("ば", "ba", "ba", "ba"),
+    ("び", "bi", "bi", "bi"),
+    ("ぶ", "bu", "bu", "bu"),
+    ("べ", "be", "be", "be"),
+    ("ぼ", "bo", "bo", "bo"),
+    ("ぱ", "pa", "pa", "pa"),
+    ("ぴ", "pi", "pi", "pi"),
+    ("ぷ", "pu", "pu", "pu"),
+    ("ぺ", "pe", "pe", "pe"),
+    ("ぽ", "po", "po", "po"),
+    ("ま", "ma", "ma", "ma"),
+    ("み", "mi", "mi", "mi"),
+    ("む", "mu", "mu", "mu"),
+    ("め", "me", "me", "me"),
+    ("も", "mo", "mo", "mo"),
+    ("や", "ya", "ya", "ya"),
+    ("ゆ", "yu", "yu", "yu"),
+    ("よ", "yo", "yo", "yo"),
+    ("ら", "ra", "ra", "ra"),
+    ("り", "ri", "ri", "ri"),
+    ("る", "ru", "ru", "ru"),
+    ("れ", "re", "re", "re"),
+    ("ろ", "ro", "ro", "ro"),
+    ("わ", "wa", "wa", "wa"),
+    ("ゐ", "i", "i", "wi"),
+    ("ゑ", "e", "e", "we"),
+    ("を", "o", "o", "wo"),
+    ("ゔ", "vu", "vu", "vu"),
+    ("ぁ", "a", "a", "a"),
+    ("ぃ", "i", "i", "i"),
+    ("ぅ", "u", "u", "u"),
+    ("ぇ", "e", "e", "e"),
+    ("ぉ", "o", "o", "o"),
+    ("ゃ", "ya", "ya", "ya"),
+    ("ゅ", "yu", "yu", "yu"),
+    ("ょ", "yo", "yo", "yo"),
+    ("ゎ", "wa", "wa", "wa"),
+];
+
+pub fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{3096}').contains(&c)
+}
+
+pub fn is_katakana(c: char) -> bool {
+    ('\u{30A1}'..='\u{30F6}').contains(&c)
+}
+
+fn hiragana_of(c: char) -> char {
+    if is_katakana(c) {
+        char::from_u32(c as u32 - KATAKANA_OFFSET).unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+fn katakana_of(c: char) -> char {
+    if is_hiragana(c) {
+        char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+// Small kana join the mora before them, っ, ん and ー count on their own
+const SMALL_KANA: &[char] = &['ゃ', 'ゅ', 'ょ', 'ぁ', 'ぃ', 'ぅ', 'ぇ', 'ぉ', 'ゎ'];
+
+pub fn mora_count(text: &str) -> usize {
+    to_hiragana(text)
+        .chars()
+        .filter(|c| !SMALL_KANA.contains(c))
+        .count()
+}
+
+// Every small kana with the full-size kana it is a small form of
+const SMALL_TO_LARGE: &[(char, char)] = &[
+    ('ぁ', 'あ'),
+    ('ぃ', 'い'),
+    ('ぅ', 'う'),
+    ('ぇ', 'え'),
+    ('ぉ', 'お'),
+    ('っ', 'つ'),
+    ('ゃ', 'や'),
+    ('ゅ', 'ゆ'),
+    ('ょ', 'よ'),
+    ('ゎ', 'わ'),
+    ('ゕ', 'か'),
+    ('ゖ', 'け'),
+];
+
+pub fn is_small_kana(c: char) -> bool {
+    let c = hiragana_of(c);
+    SMALL_TO_LARGE.iter().any(|(small, _)| *small == c)
+}
+
+// Small form of a kana in the same script, None if it has none
+pub fn small_kana(c: char) -> Option<char> {
+    let (small, _) = SMALL_TO_LARGE
+        .iter()
+        .find(|(_, large)| *large == hiragana_of(c))?;
+    Some(if is_katakana(c) {
+        katakana_of(*small)
+    } else {
+        *small
+    })
+}
+
+pub fn to_large_kana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            match SMALL_TO_LARGE
+                .iter()
+                .find(|(small, _)| *small == hiragana_of(c))
+            {
+                Some((_, large)) if is_katakana(c) => katakana_of(*large),
+                Some((_, large)) => *large,
+                None => c,
+            }
+        })
+        .collect()
+}
+
+// U+FF61 to U+FF9F in order, followed by their full-width forms
+const HALF_WIDTH: &str = "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝﾞﾟ";
+const FULL_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+// Kana whose voiced form is the next code point, and the half-voiced form the one after
+const VOICEABLE: &str =
+    "かきくけこさしすせそたちつてとはひふへほカキクケコサシスセソタチツテトハヒフヘホ";
+const HALF_VOICEABLE: &str = "はひふへほハヒフヘホ";
+
+// Spacing, half-width and combining voicing marks are all accepted
+fn voiced(c: char, mark: char) -> Option<char> {
+    match mark {
+        '゛' | '\u{FF9E}' | '\u{3099}' => match c {
+            'う' => Some('ゔ'),
+            'ウ' => Some('ヴ'),
+            'ワ' => Some('ヷ'),
+            'ヲ' => Some('ヺ'),
+            _ if VOICEABLE.contains(c) => char::from_u32(c as u32 + 1),
+            _ => None,
+        },
+        '゜' | '\u{FF9F}' | '\u{309A}' if HALF_VOICEABLE.contains(c) => {
+            char::from_u32(c as u32 + 2)
+        }
+        _ => None,
+    }
+}
+
+// Half-width katakana become full-width and voicing marks are merged into the kana before them
+pub fn to_full_width(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        let c = HALF_WIDTH
+            .chars()
+            .position(|x| x == c)
+            .and_then(|i| FULL_WIDTH.chars().nth(i))
+            .unwrap_or(c);
+        match result.chars().last().and_then(|last| voiced(last, c)) {
+            Some(voiced) => {
+                result.pop();
+                result.push(voiced);
+            }
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+fn vowel_of(c: char) -> Option<char> {
+    let romaji = ROMAJI_TABLE
+        .iter()
+        .find(|(kana, ..)| kana.chars().eq(std::iter::once(c)))?
+        .1;
+    match romaji.chars().last()? {
+        'a' => Some('あ'),
+        'i' => Some('い'),
+        'u' => Some('う'),
+        'e' => Some('え'),
+        'o' => Some('お'),
+        _ => None,
+    }
+}
+
+// ー repeats the vowel of the kana before it in the same script, after ん, っ or nothing it stays
+pub fn expand_long_vowels(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == 'ー' {
+            let last = result.chars().last();
+            if let Some((last, vowel)) = last.and_then(|x| Some((x, vowel_of(hiragana_of(x))?))) {
+                result.push(if is_katakana(last) {
+                    katakana_of(vowel)
+                } else {
+                    vowel
+                });
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+pub fn to_hiragana(text: &str) -> String {
+    expand_long_vowels(&text.chars().map(hiragana_of).collect::<String>())
+}
+
+pub fn to_katakana(text: &str) -> String {
+    text.chars().map(katakana_of).collect()
+}
+
+pub fn to_romaji(text: &str, system: RomajiSystem) -> String {
+    let hiragana = to_hiragana(text);
+    let mut rest = hiragana.as_str();
+    let mut result = String::with_capacity(hiragana.len() * 2);
+    let mut geminate = false;
+    while let Some(c) = rest.chars().next() {
+        if c == 'っ' {
+            geminate = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if c == 'ん' {
+            rest = &rest[c.len_utf8()..];
+            result.push('n');
+            let next = to_romaji_syllable(rest, system).map(|(romaji, _)| romaji);
+            if system == RomajiSystem::Hepburn
+                && matches!(
+                    next.and_then(|x| x.chars().next()),
+                    Some('a' | 'i' | 'u' | 'e' | 'o' | 'y')
+                )
+            {
+                result.push('\'');
+            }
+            continue;
+        }
+        match to_romaji_syllable(rest, system) {
+            Some((romaji, len)) => {
+                if geminate {
+                    if system == RomajiSystem::Hepburn && romaji.starts_with("ch") {
+                        result.push('t');
+                    } else if let Some(first) = romaji.chars().next() {
+                        result.push(first);
+                    }
+                }
+                result.push_str(romaji);
+                rest = &rest[len..];
+            }
+            None => {
+                if geminate {
+                    result.push('っ');
+                }
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        geminate = false;
+    }
+    result
+}
+
+fn to_romaji_syllable(text: &str, system: RomajiSystem) -> Option<(&'static str, usize)> {
+    ROMAJI_TABLE
+        .iter()
+        .find(|(kana, ..)| text.starts_with(kana))
+        .map(|(kana, hepburn, kunrei, nihon_shiki)| match system {
+            RomajiSystem::Hepburn => (*hepburn, kana.len()),
+            RomajiSystem::Kunrei => (*kunrei, kana.len()),
+            RomajiSystem::NihonShiki => (*nihon_shiki, kana.len()),
+        })
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+// Spellings shared by several kana read as the plain gojuon kana: ti is ち, not てぃ, and wo is を
+fn reverse_rank(kana: &str) -> u8 {
+    match kana {
+        "ゐ" | "ゑ" => 3,
+        _ if kana.chars().count() > 1 => 1,
+        _ if kana.chars().all(is_small_kana) => 2,
+        _ => 0,
+    }
+}
+
+fn kana_for_romaji(romaji: &str) -> Option<&'static str> {
+    ROMAJI_TABLE
+        .iter()
+        .filter(|(_, hepburn, kunrei, nihon_shiki)| {
+            *hepburn == romaji || *kunrei == romaji || *nihon_shiki == romaji
+        })
+        .min_by_key(|(kana, ..)| reverse_rank(kana))
+        .map(|(kana, ..)| *kana)
+}
+
+// Reads Hepburn, Kunrei and Nihon-shiki alike into hiragana, text that is not romaji is kept
+pub fn from_romaji(text: &str) -> String {
+    let chars = text.to_lowercase().chars().collect::<Vec<_>>();
+    let starts_syllable = |c: Option<char>| matches!(c, Some(c) if is_vowel(c) || c == 'y');
+    let mut result = String::with_capacity(text.len() * 3);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == 'n' && !starts_syllable(next) {
+            result.push('ん');
+            // n' and nn spell ん on their own unless the second n starts the next syllable
+            i += match next {
+                Some('\'') => 2,
+                Some('n') if !starts_syllable(chars.get(i + 2).copied()) => 2,
+                _ => 1,
+            };
+            continue;
+        }
+        let doubled = next == Some(c) || (c == 't' && next == Some('c'));
+        if doubled && c.is_ascii_lowercase() && !is_vowel(c) {
+            result.push('っ');
+            i += 1;
+            continue;
+        }
+        if c == '-' {
+            result.push('ー');
+            i += 1;
+            continue;
+        }
+        let syllable = (1..=3).rev().find_map(|length| {
+            let romaji = chars.get(i..i + length)?.iter().collect::<String>();
+            Some((kana_for_romaji(&romaji)?, length))
+        });
+        match syllable {
+            Some((kana, length)) => {
+                result.push_str(kana);
+                i += length;
+            }
+            None => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,12 @@
+// Domain logic shared by dbsetup and the app
+pub mod furigana;
+pub mod grading;
+pub mod ime;
+pub mod kana;
+pub mod mnemonic;
+pub mod normalize;
+pub mod pitch;
+pub mod review_log;
+pub mod schema;
+pub mod srs;
+pub mod tokenize;
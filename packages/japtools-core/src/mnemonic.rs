@@ -0,0 +1,315 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{SubjectData, SubjectDataOuter, SubjectMeaning};
+
+// Inline tags WaniKani uses in mnemonics and hints
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkupTag {
+    Radical,
+    Kanji,
+    Vocabulary,
+    Reading,
+    Meaning,
+    Ja,
+}
+
+impl MarkupTag {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "radical" => Some(MarkupTag::Radical),
+            "kanji" => Some(MarkupTag::Kanji),
+            "vocabulary" => Some(MarkupTag::Vocabulary),
+            "reading" => Some(MarkupTag::Reading),
+            "meaning" => Some(MarkupTag::Meaning),
+            "ja" => Some(MarkupTag::Ja),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkupTag::Radical => "radical",
+            MarkupTag::Kanji => "kanji",
+            MarkupTag::Vocabulary => "vocabulary",
+            MarkupTag::Reading => "reading",
+            MarkupTag::Meaning => "meaning",
+            MarkupTag::Ja => "ja",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarkupNode {
+    Text {
+        text: String,
+    },
+    Tag {
+        tag: MarkupTag,
+        // Only set for radical, kanji and vocabulary tags that name a known subject
+        subject_id: Option<u32>,
+        children: Vec<MarkupNode>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    UnknownTag { name: String, position: usize },
+    Unclosed { tag: MarkupTag, position: usize },
+    UnexpectedClose { name: String, position: usize },
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::UnknownTag { name, position } => {
+                write!(f, "unknown tag <{}> at byte {}", name, position)
+            }
+            MarkupError::Unclosed { tag, position } => {
+                write!(f, "<{}> at byte {} is never closed", tag.name(), position)
+            }
+            MarkupError::UnexpectedClose { name, position } => {
+                write!(f, "unexpected </{}> at byte {}", name, position)
+            }
+        }
+    }
+}
+
+impl Error for MarkupError {}
+
+// A '<' only starts a tag when a tag name or '/' follows, "a < b" stays text
+fn tag_at(text: &str) -> Option<(bool, &str, usize)> {
+    let rest = text.strip_prefix('<')?;
+    let (closing, rest) = match rest.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let end = rest.find('>')?;
+    let name = &rest[..end];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((closing, name, end + 2 + closing as usize))
+}
+
+fn push_text(nodes: &mut Vec<MarkupNode>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match nodes.last_mut() {
+        Some(MarkupNode::Text { text: last }) => last.push_str(text),
+        _ => nodes.push(MarkupNode::Text {
+            text: text.to_string(),
+        }),
+    }
+}
+
+pub fn parse_markup(text: &str) -> Result<Vec<MarkupNode>, MarkupError> {
+    // Open tags with their position and the nodes collected before them
+    let mut stack: Vec<(MarkupTag, usize, Vec<MarkupNode>)> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = text[position..].find('<') {
+        let start = position + offset;
+        push_text(&mut nodes, &text[position..start]);
+        let (closing, name, length) = match tag_at(&text[start..]) {
+            Some(tag) => tag,
+            None => {
+                push_text(&mut nodes, "<");
+                position = start + 1;
+                continue;
+            }
+        };
+        let tag = MarkupTag::from_name(name).ok_or_else(|| MarkupError::UnknownTag {
+            name: name.to_string(),
+            position: start,
+        })?;
+        if closing {
+            match stack.pop() {
+                Some((open, _, mut parent)) if open == tag => {
+                    parent.push(MarkupNode::Tag {
+                        tag,
+                        subject_id: None,
+                        children: nodes,
+                    });
+                    nodes = parent;
+                }
+                _ => {
+                    return Err(MarkupError::UnexpectedClose {
+                        name: name.to_string(),
+                        position: start,
+                    })
+                }
+            }
+        } else {
+            stack.push((tag, start, nodes));
+            nodes = Vec::new();
+        }
+        position = start + length;
+    }
+    push_text(&mut nodes, &text[position..]);
+    match stack.pop() {
+        Some((tag, position, _)) => Err(MarkupError::Unclosed { tag, position }),
+        None => Ok(nodes),
+    }
+}
+
+// Mnemonics name subjects by meaning or characters: <radical>ground</radical>, <kanji>One</kanji>
+#[derive(Debug, Clone, Default)]
+pub struct References {
+    // None marks names shared by several subjects of the same type
+    names: HashMap<(MarkupTag, String), Option<u32>>,
+}
+
+impl References {
+    fn add(&mut self, tag: MarkupTag, name: &str, subject_id: u32) {
+        let key = (tag, name.trim().to_lowercase());
+        match self.names.get(&key) {
+            Some(Some(existing)) if *existing != subject_id => {
+                self.names.insert(key, None);
+            }
+            Some(_) => {}
+            None => {
+                self.names.insert(key, Some(subject_id));
+            }
+        }
+    }
+
+    fn add_subject(
+        &mut self,
+        tag: MarkupTag,
+        id: u32,
+        characters: Option<&str>,
+        meanings: &[SubjectMeaning],
+    ) {
+        if let Some(characters) = characters {
+            self.add(tag, characters, id);
+        }
+        for meaning in meanings {
+            self.add(tag, &meaning.meaning, id);
+        }
+    }
+
+    pub fn from_subjects<'a>(subjects: impl IntoIterator<Item = &'a SubjectDataOuter>) -> Self {
+        let mut references = References::default();
+        for subject in subjects {
+            match &subject.data {
+                SubjectData::Radical(data) => references.add_subject(
+                    MarkupTag::Radical,
+                    subject.id,
+                    data.characters.as_deref(),
+                    &data.meanings,
+                ),
+                SubjectData::Kanji(data) => references.add_subject(
+                    MarkupTag::Kanji,
+                    subject.id,
+                    Some(&data.characters),
+                    &data.meanings,
+                ),
+                SubjectData::Vocabulary(data) => references.add_subject(
+                    MarkupTag::Vocabulary,
+                    subject.id,
+                    Some(&data.characters),
+                    &data.meanings,
+                ),
+                SubjectData::KanaVocabulary(_) => {}
+            }
+        }
+        references
+    }
+
+    pub fn lookup(&self, tag: MarkupTag, name: &str) -> Option<u32> {
+        self.names
+            .get(&(tag, name.trim().to_lowercase()))
+            .copied()
+            .flatten()
+    }
+
+    pub fn resolve(&self, nodes: &mut [MarkupNode]) {
+        for node in nodes {
+            if let MarkupNode::Tag {
+                tag,
+                subject_id,
+                children,
+            } = node
+            {
+                if matches!(
+                    tag,
+                    MarkupTag::Radical | MarkupTag::Kanji | MarkupTag::Vocabulary
+                ) {
+                    *subject_id = self.lookup(*tag, &to_plain_text(children));
+                }
+                self.resolve(children);
+            }
+        }
+    }
+}
+
+pub fn to_plain_text(nodes: &[MarkupNode]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            MarkupNode::Text { text } => out.push_str(text),
+            MarkupNode::Tag { children, .. } => out.push_str(&to_plain_text(children)),
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Tags become spans with a class per tag, <ja> also gets lang="ja"
+pub fn to_html(nodes: &[MarkupNode]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            MarkupNode::Text { text } => out.push_str(&escape_html(text)),
+            MarkupNode::Tag {
+                tag,
+                subject_id,
+                children,
+            } => {
+                out.push_str(&format!("<span class=\"mnemonic-{}\"", tag.name()));
+                if *tag == MarkupTag::Ja {
+                    out.push_str(" lang=\"ja\"");
+                }
+                if let Some(subject_id) = subject_id {
+                    out.push_str(&format!(" data-subject-id=\"{}\"", subject_id));
+                }
+                out.push('>');
+                out.push_str(&to_html(children));
+                out.push_str("</span>");
+            }
+        }
+    }
+    out
+}
+
+// A mnemonic or hint ready for display, with its subject references resolved
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct RenderedMnemonic {
+    pub nodes: Vec<MarkupNode>,
+    pub html: String,
+    pub plain_text: String,
+}
+
+pub fn render_mnemonic(
+    text: &str,
+    references: &References,
+) -> Result<RenderedMnemonic, MarkupError> {
+    let mut nodes = parse_markup(text)?;
+    references.resolve(&mut nodes);
+    Ok(RenderedMnemonic {
+        html: to_html(&nodes),
+        plain_text: to_plain_text(&nodes),
+        nodes,
+    })
+}
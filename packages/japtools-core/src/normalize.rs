@@ -0,0 +1,101 @@
+use itertools::Itertools;
+
+use crate::{
+    furigana::SegmentKind,
+    kana::{to_hiragana, to_katakana, to_romaji, RomajiSystem},
+    schema::VocabularyReading,
+};
+
+// Drops the う or お that lengthens the mora before it, so こう, こお and きょう read ko and kyo
+fn collapse_long_vowels(hiragana: &str) -> String {
+    let mut collapsed = String::new();
+    let mut vowel = None;
+    for c in hiragana.chars() {
+        if matches!((vowel, c), (Some('o'), 'う' | 'お') | (Some('u'), 'う')) {
+            vowel = None;
+            continue;
+        }
+        collapsed.push(c);
+        vowel = to_romaji(&c.to_string(), RomajiSystem::Hepburn)
+            .chars()
+            .last();
+    }
+    collapsed
+}
+
+fn keys(reading: &str, short: &str) -> [String; 7] {
+    let hiragana = to_hiragana(reading);
+    let katakana = to_katakana(&hiragana);
+    let katakana_long = to_katakana(reading);
+    let hepburn = to_romaji(&hiragana, RomajiSystem::Hepburn);
+    let kunrei = to_romaji(&hiragana, RomajiSystem::Kunrei);
+    let hepburn_plain = hepburn.replace('\'', "");
+    let hepburn_short = to_romaji(short, RomajiSystem::Hepburn).replace('\'', "");
+    [
+        hiragana,
+        katakana,
+        katakana_long,
+        hepburn,
+        hepburn_plain,
+        hepburn_short,
+        kunrei,
+    ]
+}
+
+fn sorted_keys(keys: impl IntoIterator<Item = String>) -> Vec<String> {
+    keys.into_iter()
+        .filter(|key| !key.is_empty())
+        .unique()
+        .sorted()
+        .collect_vec()
+}
+
+// Each reading is one morpheme, like the readings of a kanji
+pub fn reading_keys<'a>(readings: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    sorted_keys(readings.into_iter().flat_map(|reading| {
+        let reading = reading.trim();
+        keys(reading, &collapse_long_vowels(&to_hiragana(reading)))
+    }))
+}
+
+// Long vowels are only shortened inside the reading of one kanji span, so the う of
+// 思う or 追う stays. Readings without furigana are not shortened at all
+pub fn vocabulary_reading_keys(readings: &[VocabularyReading]) -> Vec<String> {
+    sorted_keys(readings.iter().flat_map(|reading| {
+        let short = reading
+            .furigana
+            .iter()
+            .map(|segment| match segment.kind {
+                SegmentKind::None => to_hiragana(&segment.reading),
+                _ => collapse_long_vowels(&to_hiragana(&segment.reading)),
+            })
+            .collect::<String>();
+        let short = if short.is_empty() {
+            to_hiragana(reading.reading.trim())
+        } else {
+            short
+        };
+        keys(reading.reading.trim(), &short)
+    }))
+}
+
+pub fn meaning_key(meaning: &str) -> String {
+    meaning
+        .to_lowercase()
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '’'))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .join(" ")
+}
+
+pub fn meaning_keys<'a>(meanings: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    meanings
+        .into_iter()
+        .map(meaning_key)
+        .filter(|key| !key.is_empty())
+        .unique()
+        .sorted()
+        .collect_vec()
+}
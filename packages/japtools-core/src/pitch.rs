@@ -0,0 +1,19 @@
+use crate::kana::mora_count;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PitchPattern {
+    Heiban,
+    Atamadaka,
+    Nakadaka,
+    Odaka,
+}
+
+// The downstep is the mora after which the pitch falls, 0 means it never falls
+pub fn pitch_pattern(reading: &str, downstep: u32) -> PitchPattern {
+    match downstep as usize {
+        0 => PitchPattern::Heiban,
+        1 => PitchPattern::Atamadaka,
+        n if n >= mora_count(reading) => PitchPattern::Odaka,
+        _ => PitchPattern::Nakadaka,
+    }
+}
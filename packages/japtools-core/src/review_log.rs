@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    grading::{AnswerType, Verdict},
+    srs::{Review, Schedule, Scheduler},
+};
+
+// One answer given in a review session, as stored in the ReviewLog table
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ReviewLogEntry {
+    pub subject_id: u32,
+    pub reviewed_at: DateTime<Utc>,
+    pub answer_type: AnswerType,
+    pub given_answer: String,
+    pub verdict: Verdict,
+    // Skill level before and after the answer, equal until the subject is completed
+    pub previous_level: u32,
+    pub new_level: u32,
+    pub response_time_ms: u32,
+}
+
+// Answers to one subject closer together than this belong to the same review,
+// every system waits longer than that between reviews
+const SESSION_GAP_MINUTES: i64 = 60;
+
+// Groups the answers of one subject into reviews, a review happens when its last answer was given
+pub fn group_reviews(entries: &[ReviewLogEntry]) -> Vec<Review> {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|x| x.reviewed_at);
+    let mut sessions: Vec<Vec<&ReviewLogEntry>> = Vec::new();
+    for entry in entries {
+        match sessions.last_mut() {
+            Some(session)
+                if entry.reviewed_at - session[session.len() - 1].reviewed_at
+                    < Duration::minutes(SESSION_GAP_MINUTES) =>
+            {
+                session.push(entry)
+            }
+            _ => sessions.push(vec![entry]),
+        }
+    }
+    sessions
+        .iter()
+        .map(|session| {
+            Review::from_verdicts(
+                session[session.len() - 1].reviewed_at,
+                &session.iter().map(|x| x.verdict).collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayedProgress {
+    pub subject_id: u32,
+    pub schedule: Schedule,
+    // When the replayed stage last changed, None if no review moved it
+    pub last_level_change: Option<DateTime<Utc>>,
+}
+
+// Rebuilds the progress of every subject in a user's history. Lessons are not logged,
+// so the first answer to a subject stands in for finishing its lesson
+pub fn replay_history(scheduler: &Scheduler, entries: &[ReviewLogEntry]) -> Vec<ReplayedProgress> {
+    let mut subjects: BTreeMap<u32, Vec<ReviewLogEntry>> = BTreeMap::new();
+    for entry in entries {
+        subjects
+            .entry(entry.subject_id)
+            .or_default()
+            .push(entry.clone());
+    }
+    subjects
+        .into_iter()
+        .map(|(subject_id, entries)| {
+            let lesson_finished_at = entries
+                .iter()
+                .map(|x| x.reviewed_at)
+                .min()
+                .unwrap_or_default();
+            let reviews = group_reviews(&entries);
+            let schedules = scheduler.replay_steps(lesson_finished_at, &reviews);
+            let last_level_change = reviews
+                .iter()
+                .zip(schedules.windows(2))
+                .rev()
+                .find(|(_, x)| x[0].stage != x[1].stage)
+                .map(|(review, _)| review.reviewed_at);
+            ReplayedProgress {
+                subject_id,
+                schedule: schedules[schedules.len() - 1],
+                last_level_change,
+            }
+        })
+        .collect()
+}
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{
+    furigana::{FuriganaSegment, ReadingComposition},
+    tokenize::SentenceToken,
+};
+
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct CharacterImageMetadataSvg {
     pub inline_styles: bool,
@@ -43,7 +48,7 @@ pub struct AuxiliaryMeaning {
     pub meaning_type: String,
 }
 
-#[derive(Deserialize, Debug, Serialize, Clone)]
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ReadingType {
     Onyomi,
@@ -65,6 +70,14 @@ pub struct VocabularyReading {
     pub accepted_answer: bool,
     pub primary: bool,
     pub reading: String,
+    // Downstep positions from the pitch accent dataset, not part of the WaniKani payload
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pitch_accents: Vec<u32>,
+    // Computed together by furigana::analyze_reading before upload
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub furigana: Vec<FuriganaSegment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composition: Option<ReadingComposition>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -104,12 +117,30 @@ pub struct KanjiData {
     pub slug: String,
     pub spaced_repetition_system_id: u32,
     pub visually_similar_subject_ids: Vec<u32>,
+    // Filled from KanjiVG, not part of the WaniKani payload
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stroke_paths: Vec<String>,
+    // Imported from Tatoeba, best ranked first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub example_sentences: Vec<ExampleSentence>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct ContextSentence {
     pub en: String,
     pub ja: String,
+    // Filled by tokenize::Dictionary before upload
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<SentenceToken>,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct ExampleSentence {
+    pub tatoeba_id: u32,
+    pub en: String,
+    pub ja: String,
+    pub rank: u32,
+    pub tokens: Vec<SentenceToken>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -148,6 +179,9 @@ pub struct VocabularyData {
     pub readings: Vec<VocabularyReading>,
     pub slug: String,
     pub spaced_repetition_system_id: u32,
+    // Imported from Tatoeba, best ranked first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub example_sentences: Vec<ExampleSentence>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -157,7 +191,22 @@ pub enum SubjectData {
     Radical(RadicalData),
     Kanji(KanjiData),
     Vocabulary(VocabularyData),
-    Kana_Vocabulary(Value),
+    #[serde(rename = "kana_vocabulary")]
+    KanaVocabulary(Value),
+}
+
+impl SubjectData {
+    pub fn level(&self) -> Option<u32> {
+        match self {
+            SubjectData::Radical(data) => Some(data.level),
+            SubjectData::Kanji(data) => Some(data.level),
+            SubjectData::Vocabulary(data) => Some(data.level),
+            SubjectData::KanaVocabulary(data) => data
+                .get("level")
+                .and_then(Value::as_u64)
+                .map(|level| level as u32),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
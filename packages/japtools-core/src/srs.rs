@@ -0,0 +1,324 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::grading::Verdict;
+
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct SrsStage {
+    pub position: u32,
+    // Seconds until the next review, None for the lesson and burned stages
+    pub interval: Option<i64>,
+    pub interval_unit: Option<String>,
+}
+
+// The data of a WaniKani /spaced_repetition_systems object
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct SpacedRepetitionSystem {
+    pub name: String,
+    pub unlocking_stage_position: u32,
+    pub starting_stage_position: u32,
+    pub passing_stage_position: u32,
+    pub burning_stage_position: u32,
+    pub stages: Vec<SrsStage>,
+}
+
+// (position, interval in hours) of WaniKani's default system
+const WANIKANI_STAGES: &[(u32, Option<i64>)] = &[
+    (0, None),
+    (1, Some(4)),
+    (2, Some(8)),
+    (3, Some(23)),
+    (4, Some(47)),
+    (5, Some(167)),
+    (6, Some(335)),
+    (7, Some(719)),
+    (8, Some(2879)),
+    (9, None),
+];
+
+impl SpacedRepetitionSystem {
+    pub fn wanikani() -> Self {
+        Self {
+            name: "Wanikani".to_string(),
+            unlocking_stage_position: 0,
+            starting_stage_position: 1,
+            passing_stage_position: 5,
+            burning_stage_position: 9,
+            stages: WANIKANI_STAGES
+                .iter()
+                .map(|(position, hours)| SrsStage {
+                    position: *position,
+                    interval: hours.map(|x| x * 3600),
+                    interval_unit: hours.map(|_| "seconds".to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn interval(&self, stage: u32) -> Option<Duration> {
+        self.stages
+            .iter()
+            .find(|x| x.position == stage)?
+            .interval
+            .map(Duration::seconds)
+    }
+
+    // Each two wrong answers cost a stage, twice as much once the item has passed
+    pub fn next_stage(&self, stage: u32, incorrect_answers: u32) -> u32 {
+        if incorrect_answers == 0 {
+            return (stage + 1).min(self.burning_stage_position);
+        }
+        let penalty = if stage >= self.passing_stage_position {
+            2
+        } else {
+            1
+        };
+        stage
+            .saturating_sub(incorrect_answers.div_ceil(2) * penalty)
+            .max(self.starting_stage_position)
+    }
+
+    // Reviews become available at the start of the hour, burned items never again
+    pub fn next_review(&self, stage: u32, reviewed_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let due = reviewed_at + self.interval(stage)?;
+        Some(due.duration_trunc(Duration::hours(1)).unwrap_or(due))
+    }
+
+    // Last stage whose interval fits into the given one, for models without stages
+    pub fn stage_for_interval(&self, interval: Duration) -> u32 {
+        self.stages
+            .iter()
+            .filter(|x| matches!(x.interval, Some(seconds) if seconds <= interval.num_seconds()))
+            .map(|x| x.position)
+            .max()
+            .unwrap_or(self.starting_stage_position)
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct Review {
+    pub reviewed_at: DateTime<Utc>,
+    // Wrong meaning and reading answers before the item was completed
+    pub incorrect_answers: u32,
+    // Passed with a typo somewhere
+    pub close: bool,
+}
+
+impl Review {
+    // Retries for the wrong reading type are not mistakes
+    pub fn from_verdicts(reviewed_at: DateTime<Utc>, verdicts: &[Verdict]) -> Self {
+        Self {
+            reviewed_at,
+            incorrect_answers: verdicts
+                .iter()
+                .filter(|x| !x.is_correct() && !x.should_retry())
+                .count() as u32,
+            close: verdicts.contains(&Verdict::Close),
+        }
+    }
+
+    pub fn rating(&self) -> Rating {
+        match (self.incorrect_answers, self.close) {
+            (0, false) => Rating::Good,
+            (0, true) => Rating::Hard,
+            _ => Rating::Again,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct MemoryState {
+    // Days until recall drops to 90%
+    pub stability: f64,
+    // 1 to 10
+    pub difficulty: f64,
+}
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+
+// FSRS-4.5 default parameters
+const FSRS_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fsrs {
+    pub weights: [f64; 17],
+    // Probability of recall the next review is planned for
+    pub desired_retention: f64,
+    pub maximum_interval_days: f64,
+}
+
+impl Default for Fsrs {
+    fn default() -> Self {
+        Self {
+            weights: FSRS_WEIGHTS,
+            desired_retention: 0.9,
+            maximum_interval_days: 365.0,
+        }
+    }
+}
+
+fn grade(rating: Rating) -> f64 {
+    match rating {
+        Rating::Again => 1.0,
+        Rating::Hard => 2.0,
+        Rating::Good => 3.0,
+        Rating::Easy => 4.0,
+    }
+}
+
+fn elapsed_days(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    ((to - from).num_seconds() as f64 / 86400.0).max(0.0)
+}
+
+impl Fsrs {
+    pub fn initial_state(&self, rating: Rating) -> MemoryState {
+        let w = &self.weights;
+        MemoryState {
+            stability: w[grade(rating) as usize - 1],
+            difficulty: self.initial_difficulty(rating),
+        }
+    }
+
+    fn initial_difficulty(&self, rating: Rating) -> f64 {
+        let w = &self.weights;
+        (w[4] - (grade(rating) - 3.0) * w[5]).clamp(1.0, 10.0)
+    }
+
+    pub fn retrievability(&self, state: &MemoryState, elapsed_days: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / state.stability).powf(DECAY)
+    }
+
+    pub fn interval_days(&self, state: &MemoryState) -> f64 {
+        let days = state.stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        days.clamp(1.0 / 24.0, self.maximum_interval_days)
+    }
+
+    // A late review that is still recalled raises stability more, as recall was less likely
+    pub fn next_state(
+        &self,
+        state: &MemoryState,
+        elapsed_days: f64,
+        rating: Rating,
+    ) -> MemoryState {
+        let w = &self.weights;
+        let g = grade(rating);
+        let r = self.retrievability(state, elapsed_days);
+        let difficulty = state.difficulty - w[6] * (g - 3.0);
+        // FSRS-4.5 reverts toward w[4], later versions toward the initial difficulty of Easy
+        let difficulty = (w[7] * w[4] + (1.0 - w[7]) * difficulty).clamp(1.0, 10.0);
+        let stability = match rating {
+            Rating::Again => {
+                w[11]
+                    * state.difficulty.powf(-w[12])
+                    * ((state.stability + 1.0).powf(w[13]) - 1.0)
+                    * (w[14] * (1.0 - r)).exp()
+            }
+            _ => {
+                let hard_penalty = if rating == Rating::Hard { w[15] } else { 1.0 };
+                let easy_bonus = if rating == Rating::Easy { w[16] } else { 1.0 };
+                state.stability
+                    * (w[8].exp()
+                        * (11.0 - state.difficulty)
+                        * state.stability.powf(-w[9])
+                        * ((w[10] * (1.0 - r)).exp() - 1.0)
+                        * hard_penalty
+                        * easy_bonus
+                        + 1.0)
+            }
+        };
+        MemoryState {
+            stability,
+            difficulty,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct Schedule {
+    pub stage: u32,
+    // None once burned
+    pub next_review: Option<DateTime<Utc>>,
+    // Only kept by the adaptive model
+    pub memory: Option<MemoryState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Scheduler {
+    Fixed(SpacedRepetitionSystem),
+    // Stages only describe progress here, the system maps intervals back onto them
+    Adaptive {
+        model: Fsrs,
+        system: SpacedRepetitionSystem,
+    },
+}
+
+impl Scheduler {
+    // Replays the reviews of one subject since its lesson was finished
+    pub fn replay(&self, lesson_finished_at: DateTime<Utc>, history: &[Review]) -> Schedule {
+        let schedules = self.replay_steps(lesson_finished_at, history);
+        schedules[schedules.len() - 1]
+    }
+
+    // The schedule after the lesson and then after each review, oldest first
+    pub fn replay_steps(
+        &self,
+        lesson_finished_at: DateTime<Utc>,
+        history: &[Review],
+    ) -> Vec<Schedule> {
+        let mut history = history.to_vec();
+        history.sort_by_key(|x| x.reviewed_at);
+        match self {
+            Scheduler::Fixed(system) => {
+                let schedule = |stage, reviewed_at| Schedule {
+                    stage,
+                    next_review: system.next_review(stage, reviewed_at),
+                    memory: None,
+                };
+                let mut stage = system.starting_stage_position;
+                let mut schedules = vec![schedule(stage, lesson_finished_at)];
+                for review in &history {
+                    stage = system.next_stage(stage, review.incorrect_answers);
+                    schedules.push(schedule(stage, review.reviewed_at));
+                }
+                schedules
+            }
+            Scheduler::Adaptive { model, system } => {
+                let schedule = |memory, last| {
+                    let interval =
+                        Duration::seconds((model.interval_days(&memory) * 86400.0) as i64);
+                    Schedule {
+                        stage: system
+                            .stage_for_interval(interval)
+                            .min(system.burning_stage_position - 1),
+                        next_review: Some(last + interval),
+                        memory: Some(memory),
+                    }
+                };
+                // The lesson counts as a failed first recall, so the first review comes within a day
+                let mut memory = model.initial_state(Rating::Again);
+                let mut last = lesson_finished_at;
+                let mut schedules = vec![schedule(memory, last)];
+                for review in &history {
+                    let elapsed = elapsed_days(last, review.reviewed_at);
+                    memory = model.next_state(&memory, elapsed, review.rating());
+                    last = review.reviewed_at;
+                    schedules.push(schedule(memory, last));
+                }
+                schedules
+            }
+        }
+    }
+}
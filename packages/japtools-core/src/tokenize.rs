@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kana::{is_hiragana, is_katakana};
+
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct SentenceToken {
+    pub text: String,
+    pub subject_id: Option<u32>,
+}
+
+// Kana a conjugated stem can end in, keyed by the last kana of the dictionary form
+const STEM_ENDINGS: &[(char, &str)] = &[
+    ('う', "わいうえおっ"),
+    ('く', "かきくけこいっ"),
+    ('ぐ', "がぎぐげごい"),
+    ('す', "さしすせそ"),
+    ('つ', "たちつてとっ"),
+    ('ぬ', "なにぬねのん"),
+    ('ぶ', "ばびぶべぼん"),
+    ('む', "まみむめもん"),
+    ('る', "らりるれろっ"),
+    ('い', "いくかけさ"),
+];
+
+// Ichidan verbs drop る without changing anything: 食べ + ない
+const BARE_STEMS: &[char] = &['る'];
+
+// Endings that can follow a conjugated stem, longest first wins
+const AUXILIARIES: &[&str] = &[
+    "ませんでした",
+    "ません",
+    "ました",
+    "ましょう",
+    "ます",
+    "なかった",
+    "なければ",
+    "ない",
+    "たかった",
+    "たい",
+    "ている",
+    "ていた",
+    "てる",
+    "たら",
+    "させる",
+    "られる",
+    "せる",
+    "れる",
+    "れば",
+    "よう",
+    "った",
+    "って",
+    "て",
+    "で",
+    "た",
+    "だ",
+    "ば",
+    "う",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Kanji,
+    Vocabulary,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    subject_id: u32,
+    priority: Priority,
+    // Dictionary form ending for stems that only match when conjugated
+    ending: Option<char>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    entries: HashMap<String, Vec<Entry>>,
+    longest: usize,
+}
+
+fn is_kana(c: char) -> bool {
+    is_hiragana(c) || is_katakana(c) || c == 'ー'
+}
+
+fn longest_auxiliary(rest: &[char]) -> usize {
+    AUXILIARIES
+        .iter()
+        .map(|x| x.chars().collect::<Vec<_>>())
+        .filter(|x| rest.starts_with(x))
+        .map(|x| x.len())
+        .max()
+        .unwrap_or_default()
+}
+
+// Length of the conjugation following a stem, None if the text does not continue like one
+fn inflection_length(ending: char, rest: &[char]) -> Option<usize> {
+    let (_, stems) = STEM_ENDINGS.iter().find(|(c, _)| *c == ending)?;
+    let changed = rest.first().filter(|c| stems.contains(**c));
+    match changed {
+        Some(_) => Some(1 + longest_auxiliary(&rest[1..])),
+        None if BARE_STEMS.contains(&ending) => match longest_auxiliary(rest) {
+            0 => None,
+            length => Some(length),
+        },
+        None => None,
+    }
+}
+
+impl Dictionary {
+    fn insert(&mut self, text: String, entry: Entry) {
+        self.longest = self.longest.max(text.chars().count());
+        self.entries.entry(text).or_default().push(entry);
+    }
+
+    pub fn add_kanji(&mut self, characters: &str, subject_id: u32) {
+        self.insert(
+            characters.to_string(),
+            Entry {
+                subject_id,
+                priority: Priority::Kanji,
+                ending: None,
+            },
+        );
+    }
+
+    // Verbs and adjectives written with kanji are also found by their stem: 食べ, 大き
+    pub fn add_vocabulary(&mut self, characters: &str, subject_id: u32) {
+        self.insert(
+            characters.to_string(),
+            Entry {
+                subject_id,
+                priority: Priority::Vocabulary,
+                ending: None,
+            },
+        );
+        let mut chars = characters.chars();
+        let ending = chars.next_back();
+        let stem = chars.as_str();
+        if let Some(ending) = ending {
+            let conjugates = STEM_ENDINGS.iter().any(|(c, _)| *c == ending);
+            if conjugates && stem.chars().any(|c| !is_kana(c)) {
+                self.insert(
+                    stem.to_string(),
+                    Entry {
+                        subject_id,
+                        priority: Priority::Vocabulary,
+                        ending: Some(ending),
+                    },
+                );
+            }
+        }
+    }
+
+    // (length, priority, subject id) of the best word starting the text
+    fn longest_match(&self, text: &[char]) -> Option<(usize, Priority, u32)> {
+        let mut best: Option<(usize, Priority, u32)> = None;
+        for length in (1..=self.longest.min(text.len())).rev() {
+            let key = text[..length].iter().collect::<String>();
+            for entry in self.entries.get(&key).into_iter().flatten() {
+                let total = match entry.ending {
+                    Some(ending) => match inflection_length(ending, &text[length..]) {
+                        Some(inflection) => length + inflection,
+                        None => continue,
+                    },
+                    None => length,
+                };
+                let candidate = (total, entry.priority, entry.subject_id);
+                if !matches!(best, Some(best) if (best.0, best.1) >= (total, entry.priority)) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best
+    }
+
+    // Greedy longest match from left to right, text without a known word is kept as is
+    pub fn tokenize(&self, sentence: &str) -> Vec<SentenceToken> {
+        let chars = sentence.chars().collect::<Vec<_>>();
+        let mut tokens: Vec<SentenceToken> = Vec::new();
+        let mut position = 0;
+        while position < chars.len() {
+            match self.longest_match(&chars[position..]) {
+                Some((length, _, subject_id)) => {
+                    tokens.push(SentenceToken {
+                        text: chars[position..position + length].iter().collect(),
+                        subject_id: Some(subject_id),
+                    });
+                    position += length;
+                }
+                None => {
+                    match tokens.last_mut() {
+                        Some(last) if last.subject_id.is_none() => last.text.push(chars[position]),
+                        _ => tokens.push(SentenceToken {
+                            text: chars[position].to_string(),
+                            subject_id: None,
+                        }),
+                    }
+                    position += 1;
+                }
+            }
+        }
+        tokens
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use japtools_core::{
+    furigana::{analyze_reading, FuriganaSegment, ReadingComposition, SegmentKind},
+    schema::{KanjiReading, ReadingType},
+};
+
+fn kanji_readings() -> HashMap<char, Vec<KanjiReading>> {
+    let reading = |reading: &str, reading_type| KanjiReading {
+        accepted_answer: true,
+        primary: false,
+        reading: reading.to_string(),
+        reading_type,
+    };
+    [
+        ('学', vec!["がく"], vec!["まな"]),
+        ('生', vec!["せい", "しょう"], vec!["い", "う", "なま"]),
+        ('手', vec!["しゅ"], vec!["て"]),
+        ('紙', vec!["し"], vec!["かみ"]),
+        ('校', vec!["こう"], vec![]),
+        ('今', vec!["こん", "きん"], vec!["いま"]),
+        ('日', vec!["にち", "じつ"], vec!["ひ", "か"]),
+        ('食', vec!["しょく"], vec!["た", "く"]),
+        ('大', vec!["だい", "たい"], vec!["おお"]),
+        ('人', vec!["じん", "にん"], vec!["ひと"]),
+        ('一', vec!["いち", "いつ"], vec!["ひと"]),
+    ]
+    .into_iter()
+    .map(|(kanji, onyomi, kunyomi)| {
+        let readings = onyomi
+            .into_iter()
+            .map(|x| reading(x, ReadingType::Onyomi))
+            .chain(
+                kunyomi
+                    .into_iter()
+                    .map(|x| reading(x, ReadingType::Kunyomi)),
+            )
+            .collect();
+        (kanji, readings)
+    })
+    .collect()
+}
+
+fn furigana(
+    characters: &str,
+    reading: &str,
+    readings: &HashMap<char, Vec<KanjiReading>>,
+) -> Option<Vec<FuriganaSegment>> {
+    analyze_reading(characters, reading, readings).map(|x| x.furigana)
+}
+
+fn composition(characters: &str, reading: &str) -> ReadingComposition {
+    analyze_reading(characters, reading, &kanji_readings())
+        .unwrap()
+        .composition
+}
+
+fn segments(expected: &[(&str, &str, SegmentKind)]) -> Vec<FuriganaSegment> {
+    expected
+        .iter()
+        .map(|(text, reading, kind)| FuriganaSegment {
+            text: text.to_string(),
+            reading: reading.to_string(),
+            kind: *kind,
+        })
+        .collect()
+}
+
+#[test]
+fn aligns_kanji_readings() {
+    let readings = kanji_readings();
+    assert_eq!(
+        furigana("学生", "がくせい", &readings).unwrap(),
+        segments(&[
+            ("学", "がく", SegmentKind::Onyomi),
+            ("生", "せい", SegmentKind::Onyomi)
+        ])
+    );
+    assert_eq!(
+        furigana("食べる", "たべる", &readings).unwrap(),
+        segments(&[
+            ("食", "た", SegmentKind::Kunyomi),
+            ("べる", "べる", SegmentKind::None)
+        ])
+    );
+}
+
+#[test]
+fn aligns_sound_changes() {
+    let readings = kanji_readings();
+    // Rendaku
+    assert_eq!(
+        furigana("手紙", "てがみ", &readings).unwrap(),
+        segments(&[
+            ("手", "て", SegmentKind::Kunyomi),
+            ("紙", "がみ", SegmentKind::Kunyomi)
+        ])
+    );
+    // Gemination
+    assert_eq!(
+        furigana("学校", "がっこう", &readings).unwrap(),
+        segments(&[
+            ("学", "がっ", SegmentKind::Onyomi),
+            ("校", "こう", SegmentKind::Onyomi)
+        ])
+    );
+}
+
+#[test]
+fn merges_irregular_spans() {
+    let readings = kanji_readings();
+    assert_eq!(
+        furigana("今日", "きょう", &readings).unwrap(),
+        segments(&[("今日", "きょう", SegmentKind::Irregular)])
+    );
+}
+
+#[test]
+fn rejects_readings_that_do_not_fit() {
+    let readings = kanji_readings();
+    // The okurigana is spelled differently in the reading
+    assert_eq!(furigana("食べる", "たべた", &readings), None);
+    assert_eq!(furigana("学生", "", &readings), None);
+}
+
+#[test]
+fn classifies_compositions() {
+    assert_eq!(composition("学生", "がくせい"), ReadingComposition::Regular);
+    assert_eq!(composition("食べる", "たべる"), ReadingComposition::Regular);
+    assert_eq!(
+        composition("手紙", "てがみ"),
+        ReadingComposition::SoundChange
+    );
+    assert_eq!(
+        composition("学校", "がっこう"),
+        ReadingComposition::SoundChange
+    );
+    assert_eq!(composition("今日", "きょう"), ReadingComposition::Jukujikun);
+    assert_eq!(composition("大人", "おとな"), ReadingComposition::Jukujikun);
+    // ひと fits 一 on its own, but り is no reading of 人
+    assert_eq!(composition("一人", "ひとり"), ReadingComposition::Jukujikun);
+    assert_eq!(
+        analyze_reading("一人", "ひとり", &kanji_readings())
+            .unwrap()
+            .furigana,
+        segments(&[("一人", "ひとり", SegmentKind::Irregular)])
+    );
+}
@@ -0,0 +1,293 @@
+use japtools_core::{
+    grading::{
+        grade, grade_kanji_reading, grade_meaning, number_to_words, reading_key, AnswerType,
+        Leniency, Verdict,
+    },
+    schema::{AuxiliaryMeaning, KanjiReading, ReadingType, SubjectDataOuter, SubjectMeaning},
+};
+
+fn fixture(name: &str) -> SubjectDataOuter {
+    let text = match name {
+        "kanji" => include_str!("fixtures/subjects/kanji.json"),
+        "radical_image_only" => include_str!("fixtures/subjects/radical_image_only.json"),
+        "vocabulary_readings" => include_str!("fixtures/subjects/vocabulary_readings.json"),
+        "kana_vocabulary" => include_str!("fixtures/subjects/kana_vocabulary.json"),
+        _ => unreachable!(),
+    };
+    serde_json::from_str(text).unwrap()
+}
+
+fn meaning(text: &str) -> SubjectMeaning {
+    SubjectMeaning {
+        accepted_answer: true,
+        meaning: text.to_string(),
+        primary: true,
+    }
+}
+
+fn auxiliary(meaning_type: &str, text: &str) -> AuxiliaryMeaning {
+    AuxiliaryMeaning {
+        meaning: text.to_string(),
+        meaning_type: meaning_type.to_string(),
+    }
+}
+
+const ONYOMI_EXPECTED: Verdict = Verdict::WrongReadingType {
+    expected: ReadingType::Onyomi,
+};
+
+// (fixture, answer type, answer, verdict)
+const FIXTURE_CASES: &[(&str, AnswerType, &str, Verdict)] = &[
+    ("kanji", AnswerType::Meaning, "one", Verdict::Correct),
+    ("kanji", AnswerType::Meaning, " One ", Verdict::Correct),
+    ("kanji", AnswerType::Meaning, "1", Verdict::Correct),
+    ("kanji", AnswerType::Meaning, "onr", Verdict::Wrong),
+    ("kanji", AnswerType::Meaning, "", Verdict::Wrong),
+    ("kanji", AnswerType::Reading, "いち", Verdict::Correct),
+    ("kanji", AnswerType::Reading, "イチ", Verdict::Correct),
+    ("kanji", AnswerType::Reading, "ichi", Verdict::Correct),
+    ("kanji", AnswerType::Reading, "ﾋﾄ", ONYOMI_EXPECTED),
+    ("kanji", AnswerType::Reading, "かず", ONYOMI_EXPECTED),
+    ("kanji", AnswerType::Reading, "いっ", Verdict::Wrong),
+    (
+        "radical_image_only",
+        AnswerType::Meaning,
+        "pistol",
+        Verdict::Correct,
+    ),
+    (
+        "radical_image_only",
+        AnswerType::Meaning,
+        "pistl",
+        Verdict::Close,
+    ),
+    (
+        "radical_image_only",
+        AnswerType::Meaning,
+        "stick",
+        Verdict::Blacklisted,
+    ),
+    (
+        "radical_image_only",
+        AnswerType::Meaning,
+        "stik",
+        Verdict::Wrong,
+    ),
+    (
+        "radical_image_only",
+        AnswerType::Reading,
+        "じゅう",
+        Verdict::Wrong,
+    ),
+    (
+        "vocabulary_readings",
+        AnswerType::Meaning,
+        "grown-up",
+        Verdict::Correct,
+    ),
+    (
+        "vocabulary_readings",
+        AnswerType::Meaning,
+        "grownup",
+        Verdict::Close,
+    ),
+    (
+        "vocabulary_readings",
+        AnswerType::Reading,
+        "おとな",
+        Verdict::Correct,
+    ),
+    (
+        "vocabulary_readings",
+        AnswerType::Reading,
+        "だいにん",
+        Verdict::OtherReading,
+    ),
+    (
+        "kana_vocabulary",
+        AnswerType::Meaning,
+        "orange",
+        Verdict::Correct,
+    ),
+    (
+        "kana_vocabulary",
+        AnswerType::Reading,
+        "おれんじ",
+        Verdict::Correct,
+    ),
+];
+
+#[test]
+fn grades_fixture_answers() {
+    for (name, answer_type, answer, verdict) in FIXTURE_CASES {
+        let subject = fixture(name);
+        assert_eq!(
+            grade(&subject.data, *answer_type, answer, &Leniency::default()),
+            *verdict,
+            "{} {:?} {:?}",
+            name,
+            answer_type,
+            answer
+        );
+    }
+}
+
+#[test]
+fn converts_romaji_answers() {
+    // A double n only stands for one ん when typed into an IME
+    assert_eq!(reading_key("onna"), "おんな");
+    assert_eq!(reading_key("minna"), "みんな");
+    assert_eq!(reading_key("konnichiha"), "こんにちは");
+    assert_eq!(reading_key("kon'ya"), "こんや");
+}
+
+#[test]
+fn typo_tolerance_grows_with_length() {
+    let leniency = Leniency::default();
+    let allowed = (1..=16).map(|x| leniency.max_typos(x)).collect::<Vec<_>>();
+    assert_eq!(allowed, [0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3]);
+
+    let meanings = [meaning("Ground"), meaning("Construction")];
+    for (answer, verdict) in [
+        ("groud", Verdict::Close),
+        ("grnd", Verdict::Wrong),
+        ("constrution", Verdict::Close),
+        ("constrcton", Verdict::Close),
+        ("cnstrcton", Verdict::Wrong),
+    ] {
+        assert_eq!(
+            grade_meaning(answer, &meanings, &[], &leniency),
+            verdict,
+            "{}",
+            answer
+        );
+    }
+
+    let strict = Leniency {
+        typo_ratio: 0.0,
+        ..Leniency::default()
+    };
+    assert_eq!(
+        grade_meaning("constrution", &meanings, &[], &strict),
+        Verdict::Wrong
+    );
+}
+
+#[test]
+fn blacklist_beats_an_equally_close_meaning() {
+    let meanings = [meaning("Dirt")];
+    let auxiliary_meanings = [auxiliary("blacklist", "Dart")];
+    let leniency = Leniency::default();
+    assert_eq!(
+        grade_meaning("dirt", &meanings, &auxiliary_meanings, &leniency),
+        Verdict::Correct
+    );
+    assert_eq!(
+        grade_meaning("dart", &meanings, &auxiliary_meanings, &leniency),
+        Verdict::Blacklisted
+    );
+
+    let meanings = [meaning("Fortune")];
+    let auxiliary_meanings = [auxiliary("blacklist", "Fortunes")];
+    assert_eq!(
+        grade_meaning("fortunez", &meanings, &auxiliary_meanings, &leniency),
+        Verdict::Blacklisted
+    );
+    assert_eq!(
+        grade_meaning("fortine", &meanings, &auxiliary_meanings, &leniency),
+        Verdict::Close
+    );
+}
+
+#[test]
+fn accepts_number_words_and_optional_words() {
+    let leniency = Leniency::default();
+    let meanings = [meaning("Twenty One"), meaning("To Do Something")];
+    for answer in ["21", "twenty-one", "to do", "to do something"] {
+        assert_eq!(
+            grade_meaning(answer, &meanings, &[], &leniency),
+            Verdict::Correct,
+            "{}",
+            answer
+        );
+    }
+    let without_numbers = Leniency {
+        number_words: false,
+        ..Leniency::default()
+    };
+    assert_eq!(
+        grade_meaning("21", &meanings, &[], &without_numbers),
+        Verdict::Wrong
+    );
+}
+
+#[test]
+fn spells_numbers() {
+    for (number, words) in [
+        (0, "zero"),
+        (7, "seven"),
+        (13, "thirteen"),
+        (40, "forty"),
+        (99, "ninety nine"),
+        (100, "one hundred"),
+        (1001, "one thousand one"),
+        (10_000, "ten thousand"),
+        (
+            123_456,
+            "one hundred twenty three thousand four hundred fifty six",
+        ),
+        (2_000_000, "two million"),
+    ] {
+        assert_eq!(number_to_words(number), words);
+    }
+}
+
+fn kanji_reading(reading: &str, reading_type: ReadingType, primary: bool) -> KanjiReading {
+    KanjiReading {
+        accepted_answer: primary,
+        primary,
+        reading: reading.to_string(),
+        reading_type,
+    }
+}
+
+#[test]
+fn asks_again_for_the_primary_reading_type() {
+    // 生: せい is primary, しょう is an onyomi that is not accepted, い and なま are kunyomi
+    let readings = [
+        kanji_reading("せい", ReadingType::Onyomi, true),
+        kanji_reading("しょう", ReadingType::Onyomi, false),
+        kanji_reading("い", ReadingType::Kunyomi, false),
+        kanji_reading("なま", ReadingType::Kunyomi, false),
+    ];
+    assert_eq!(grade_kanji_reading("せい", &readings), Verdict::Correct);
+    assert_eq!(
+        grade_kanji_reading("しょう", &readings),
+        Verdict::OtherReading
+    );
+    let verdict = grade_kanji_reading("なま", &readings);
+    assert_eq!(verdict, ONYOMI_EXPECTED);
+    assert!(verdict.should_retry() && !verdict.is_correct());
+    assert_eq!(grade_kanji_reading("き", &readings), Verdict::Wrong);
+
+    // Kanji read by kunyomi ask for it, and an onyomi answer is sent back
+    let readings = [
+        kanji_reading("おんな", ReadingType::Kunyomi, true),
+        kanji_reading("じょ", ReadingType::Onyomi, false),
+    ];
+    assert_eq!(
+        grade_kanji_reading("jo", &readings),
+        Verdict::WrongReadingType {
+            expected: ReadingType::Kunyomi
+        }
+    );
+}
+
+#[test]
+fn verdicts_serialize_for_the_app() {
+    assert_eq!(serde_json::to_string(&Verdict::Close).unwrap(), "\"close\"");
+    assert_eq!(
+        serde_json::to_string(&ONYOMI_EXPECTED).unwrap(),
+        r#"{"wrong_reading_type":{"expected":"onyomi"}}"#
+    );
+}
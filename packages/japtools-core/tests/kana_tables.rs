@@ -0,0 +1,325 @@
+use japtools_core::kana::{
+    expand_long_vowels, from_romaji, is_small_kana, small_kana, to_full_width, to_hiragana,
+    to_katakana, to_large_kana, to_romaji, RomajiSystem,
+};
+
+// (kana, hepburn, kunrei, nihon-shiki) for every syllable of the gojuon, voiced rows and yoon
+const SYLLABLES: &[(&str, &str, &str, &str)] = &[
+    ("あ", "a", "a", "a"),
+    ("い", "i", "i", "i"),
+    ("う", "u", "u", "u"),
+    ("え", "e", "e", "e"),
+    ("お", "o", "o", "o"),
+    ("か", "ka", "ka", "ka"),
+    ("き", "ki", "ki", "ki"),
+    ("く", "ku", "ku", "ku"),
+    ("け", "ke", "ke", "ke"),
+    ("こ", "ko", "ko", "ko"),
+    ("さ", "sa", "sa", "sa"),
+    ("し", "shi", "si", "si"),
+    ("す", "su", "su", "su"),
+    ("せ", "se", "se", "se"),
+    ("そ", "so", "so", "so"),
+    ("た", "ta", "ta", "ta"),
+    ("ち", "chi", "ti", "ti"),
+    ("つ", "tsu", "tu", "tu"),
+    ("て", "te", "te", "te"),
+    ("と", "to", "to", "to"),
+    ("な", "na", "na", "na"),
+    ("に", "ni", "ni", "ni"),
+    ("ぬ", "nu", "nu", "nu"),
+    ("ね", "ne", "ne", "ne"),
+    ("の", "no", "no", "no"),
+    ("は", "ha", "ha", "ha"),
+    ("ひ", "hi", "hi", "hi"),
+    ("ふ", "fu", "hu", "hu"),
+    ("へ", "he", "he", "he"),
+    ("ほ", "ho", "ho", "ho"),
+    ("ま", "ma", "ma", "ma"),
+    ("み", "mi", "mi", "mi"),
+    ("む", "mu", "mu", "mu"),
+    ("め", "me", "me", "me"),
+    ("も", "mo", "mo", "mo"),
+    ("や", "ya", "ya", "ya"),
+    ("ゆ", "yu", "yu", "yu"),
+    ("よ", "yo", "yo", "yo"),
+    ("ら", "ra", "ra", "ra"),
+    ("り", "ri", "ri", "ri"),
+    ("る", "ru", "ru", "ru"),
+    ("れ", "re", "re", "re"),
+    ("ろ", "ro", "ro", "ro"),
+    ("わ", "wa", "wa", "wa"),
+    ("を", "o", "o", "wo"),
+    ("が", "ga", "ga", "ga"),
+    ("ぎ", "gi", "gi", "gi"),
+    ("ぐ", "gu", "gu", "gu"),
+    ("げ", "ge", "ge", "ge"),
+    ("ご", "go", "go", "go"),
+    ("ざ", "za", "za", "za"),
+    ("じ", "ji", "zi", "zi"),
+    ("ず", "zu", "zu", "zu"),
+    ("ぜ", "ze", "ze", "ze"),
+    ("ぞ", "zo", "zo", "zo"),
+    ("だ", "da", "da", "da"),
+    ("ぢ", "ji", "zi", "di"),
+    ("づ", "zu", "zu", "du"),
+    ("で", "de", "de", "de"),
+    ("ど", "do", "do", "do"),
+    ("ば", "ba", "ba", "ba"),
+    ("び", "bi", "bi", "bi"),
+    ("ぶ", "bu", "bu", "bu"),
+    ("べ", "be", "be", "be"),
+    ("ぼ", "bo", "bo", "bo"),
+    ("ぱ", "pa", "pa", "pa"),
+    ("ぴ", "pi", "pi", "pi"),
+    ("ぷ", "pu", "pu", "pu"),
+    ("ぺ", "pe", "pe", "pe"),
+    ("ぽ", "po", "po", "po"),
+    ("きゃ", "kya", "kya", "kya"),
+    ("きゅ", "kyu", "kyu", "kyu"),
+    ("きょ", "kyo", "kyo", "kyo"),
+    ("ぎゃ", "gya", "gya", "gya"),
+    ("ぎゅ", "gyu", "gyu", "gyu"),
+    ("ぎょ", "gyo", "gyo", "gyo"),
+    ("しゃ", "sha", "sya", "sya"),
+    ("しゅ", "shu", "syu", "syu"),
+    ("しょ", "sho", "syo", "syo"),
+    ("じゃ", "ja", "zya", "zya"),
+    ("じゅ", "ju", "zyu", "zyu"),
+    ("じょ", "jo", "zyo", "zyo"),
+    ("ちゃ", "cha", "tya", "tya"),
+    ("ちゅ", "chu", "tyu", "tyu"),
+    ("ちょ", "cho", "tyo", "tyo"),
+    ("ぢゃ", "ja", "zya", "dya"),
+    ("ぢゅ", "ju", "zyu", "dyu"),
+    ("ぢょ", "jo", "zyo", "dyo"),
+    ("にゃ", "nya", "nya", "nya"),
+    ("にゅ", "nyu", "nyu", "nyu"),
+    ("にょ", "nyo", "nyo", "nyo"),
+    ("ひゃ", "hya", "hya", "hya"),
+    ("ひゅ", "hyu", "hyu", "hyu"),
+    ("ひょ", "hyo", "hyo", "hyo"),
+    ("びゃ", "bya", "bya", "bya"),
+    ("びゅ", "byu", "byu", "byu"),
+    ("びょ", "byo", "byo", "byo"),
+    ("ぴゃ", "pya", "pya", "pya"),
+    ("ぴゅ", "pyu", "pyu", "pyu"),
+    ("ぴょ", "pyo", "pyo", "pyo"),
+    ("みゃ", "mya", "mya", "mya"),
+    ("みゅ", "myu", "myu", "myu"),
+    ("みょ", "myo", "myo", "myo"),
+    ("りゃ", "rya", "rya", "rya"),
+    ("りゅ", "ryu", "ryu", "ryu"),
+    ("りょ", "ryo", "ryo", "ryo"),
+];
+
+// (kana, hepburn, kunrei) for spellings that depend on the surrounding kana, only Hepburn
+// marks ん before a vowel so Kunrei spellings like konya do not read back
+const WORDS: &[(&str, &str, &str)] = &[
+    ("がっこう", "gakkou", "gakkou"),
+    ("まっちゃ", "matcha", "mattya"),
+    ("きって", "kitte", "kitte"),
+    ("ざっし", "zasshi", "zassi"),
+    ("しんぶん", "shinbun", "sinbun"),
+    ("こんや", "kon'ya", "konya"),
+    ("げんいん", "gen'in", "genin"),
+    ("おんな", "onna", "onna"),
+    ("こんにちは", "konnichiha", "konnitiha"),
+    ("ラーメン", "raamen", "raamen"),
+    ("コーヒー", "koohii", "koohii"),
+    ("ファイル", "fairu", "fairu"),
+];
+
+// Romaji input with the hiragana it reads as
+const ROMAJI_INPUT: &[(&str, &str)] = &[
+    ("shinbun", "しんぶん"),
+    ("sinbun", "しんぶん"),
+    ("kon'ya", "こんや"),
+    ("konya", "こにゃ"),
+    ("konnya", "こんにゃ"),
+    ("onna", "おんな"),
+    ("konnichiha", "こんにちは"),
+    ("hon", "ほん"),
+    ("honn", "ほん"),
+    ("matcha", "まっちゃ"),
+    ("mattya", "まっちゃ"),
+    ("Gakkou", "がっこう"),
+    ("ra-men", "らーめん"),
+    ("ti", "ち"),
+    ("tu", "つ"),
+    ("wo", "を"),
+    ("wi", "うぃ"),
+    ("fairu", "ふぁいる"),
+    ("vaiorin", "ゔぁいおりん"),
+    ("kyou no tenki", "きょう の てんき"),
+    ("x", "x"),
+];
+
+const HALF_WIDTH: &[(&str, &str)] = &[
+    ("｡｢｣､･", "。「」、・"),
+    ("ｦｧｨｩｪｫｬｭｮｯｰ", "ヲァィゥェォャュョッー"),
+    ("ｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿ", "アイウエオカキクケコサシスセソ"),
+    ("ﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎ", "タチツテトナニヌネノハヒフヘホ"),
+    ("ﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ", "マミムメモヤユヨラリルレロワン"),
+    ("ｶﾞｷﾞｸﾞｹﾞｺﾞｻﾞｼﾞｽﾞｾﾞｿﾞ", "ガギグゲゴザジズゼゾ"),
+    ("ﾀﾞﾁﾞﾂﾞﾃﾞﾄﾞﾊﾞﾋﾞﾌﾞﾍﾞﾎﾞ", "ダヂヅデドバビブベボ"),
+    ("ﾊﾟﾋﾟﾌﾟﾍﾟﾎﾟ", "パピプペポ"),
+    ("ｳﾞﾜﾞｦﾞ", "ヴヷヺ"),
+    ("ｱﾞﾏﾟﾞ", "ア゛マ゜゛"),
+    ("ﾗｰﾒﾝ abc", "ラーメン abc"),
+    ("か\u{3099}は\u{309A}う\u{3099}", "がぱゔ"),
+    ("か゛", "が"),
+];
+
+const LONG_VOWELS: &[(&str, &str)] = &[
+    ("ラーメン", "ラアメン"),
+    ("らーめん", "らあめん"),
+    ("コーヒー", "コオヒイ"),
+    ("スーパー", "スウパア"),
+    ("ケーキ", "ケエキ"),
+    ("キャー", "キャア"),
+    ("ぎゅー", "ぎゅう"),
+    ("ーあ", "ーあ"),
+    ("ンー", "ンー"),
+    ("ッー", "ッー"),
+    ("abー", "abー"),
+];
+
+const SMALL_KANA: &[(char, char)] = &[
+    ('ぁ', 'あ'),
+    ('ぃ', 'い'),
+    ('ぅ', 'う'),
+    ('ぇ', 'え'),
+    ('ぉ', 'お'),
+    ('っ', 'つ'),
+    ('ゃ', 'や'),
+    ('ゅ', 'ゆ'),
+    ('ょ', 'よ'),
+    ('ゎ', 'わ'),
+    ('ゕ', 'か'),
+    ('ゖ', 'け'),
+    ('ァ', 'ア'),
+    ('ィ', 'イ'),
+    ('ゥ', 'ウ'),
+    ('ェ', 'エ'),
+    ('ォ', 'オ'),
+    ('ッ', 'ツ'),
+    ('ャ', 'ヤ'),
+    ('ュ', 'ユ'),
+    ('ョ', 'ヨ'),
+    ('ヮ', 'ワ'),
+    ('ヵ', 'カ'),
+    ('ヶ', 'ケ'),
+];
+
+#[test]
+fn script_conversion_round_trips() {
+    for c in ('\u{3041}'..='\u{3096}').filter(|c| !is_small_kana(*c)) {
+        let katakana = to_katakana(&c.to_string());
+        assert_eq!(
+            to_hiragana(&katakana),
+            c.to_string(),
+            "{} -> {}",
+            c,
+            katakana
+        );
+    }
+    for (small, _) in SMALL_KANA {
+        assert_eq!(
+            to_katakana(&to_hiragana(&small.to_string())),
+            to_katakana(&small.to_string())
+        );
+    }
+    assert_eq!(to_katakana("ひらがな ABC 漢字"), "ヒラガナ ABC 漢字");
+}
+
+#[test]
+fn romanizes_every_syllable() {
+    for (kana, hepburn, kunrei, nihon_shiki) in SYLLABLES {
+        for kana in [kana.to_string(), to_katakana(kana)] {
+            assert_eq!(
+                to_romaji(&kana, RomajiSystem::Hepburn),
+                *hepburn,
+                "{}",
+                kana
+            );
+            assert_eq!(to_romaji(&kana, RomajiSystem::Kunrei), *kunrei, "{}", kana);
+            assert_eq!(
+                to_romaji(&kana, RomajiSystem::NihonShiki),
+                *nihon_shiki,
+                "{}",
+                kana
+            );
+        }
+    }
+}
+
+#[test]
+fn reads_every_syllable_back() {
+    for (kana, hepburn, kunrei, nihon_shiki) in SYLLABLES {
+        // Nihon-shiki spells every kana differently, the other systems share spellings like ji
+        assert_eq!(from_romaji(nihon_shiki), *kana, "{}", nihon_shiki);
+        for (romaji, system) in [
+            (hepburn, RomajiSystem::Hepburn),
+            (kunrei, RomajiSystem::Kunrei),
+        ] {
+            assert_eq!(
+                to_romaji(&from_romaji(romaji), system),
+                *romaji,
+                "{}",
+                romaji
+            );
+        }
+    }
+}
+
+#[test]
+fn romanizes_words() {
+    for (kana, hepburn, kunrei) in WORDS {
+        assert_eq!(to_romaji(kana, RomajiSystem::Hepburn), *hepburn, "{}", kana);
+        assert_eq!(to_romaji(kana, RomajiSystem::Kunrei), *kunrei, "{}", kana);
+        assert_eq!(from_romaji(hepburn), to_hiragana(kana), "{}", hepburn);
+    }
+}
+
+#[test]
+fn reads_romaji_input() {
+    for (romaji, kana) in ROMAJI_INPUT {
+        assert_eq!(from_romaji(romaji), *kana, "{}", romaji);
+    }
+}
+
+#[test]
+fn widens_half_width_katakana() {
+    for (half, full) in HALF_WIDTH {
+        assert_eq!(to_full_width(half), *full, "{}", half);
+    }
+    for c in '\u{FF61}'..='\u{FF9F}' {
+        assert!(
+            to_full_width(&c.to_string())
+                .chars()
+                .all(|x| !('\u{FF61}'..='\u{FF9F}').contains(&x)),
+            "{} is left half-width",
+            c
+        );
+    }
+}
+
+#[test]
+fn expands_long_vowels() {
+    for (text, expanded) in LONG_VOWELS {
+        assert_eq!(expand_long_vowels(text), *expanded, "{}", text);
+    }
+}
+
+#[test]
+fn converts_small_kana() {
+    for (small, large) in SMALL_KANA {
+        assert!(is_small_kana(*small), "{}", small);
+        assert!(!is_small_kana(*large), "{}", large);
+        assert_eq!(small_kana(*large), Some(*small), "{}", large);
+        assert_eq!(to_large_kana(&small.to_string()), large.to_string());
+    }
+    assert_eq!(small_kana('ん'), None);
+    assert_eq!(to_large_kana("きゃっと ティー"), "きやつと テイー");
+}
@@ -0,0 +1,140 @@
+use japtools_core::{
+    mnemonic::{parse_markup, render_mnemonic, MarkupError, MarkupNode, MarkupTag, References},
+    schema::SubjectDataOuter,
+};
+
+fn fixture(name: &str) -> SubjectDataOuter {
+    let text = match name {
+        "radical" => include_str!("fixtures/subjects/radical.json"),
+        "radical_image_only" => include_str!("fixtures/subjects/radical_image_only.json"),
+        "kanji" => include_str!("fixtures/subjects/kanji.json"),
+        "vocabulary_readings" => include_str!("fixtures/subjects/vocabulary_readings.json"),
+        _ => unreachable!(),
+    };
+    serde_json::from_str(text).unwrap()
+}
+
+fn references() -> References {
+    let subjects = [
+        "radical",
+        "radical_image_only",
+        "kanji",
+        "vocabulary_readings",
+    ]
+    .map(fixture);
+    References::from_subjects(&subjects)
+}
+
+fn text(text: &str) -> MarkupNode {
+    MarkupNode::Text {
+        text: text.to_string(),
+    }
+}
+
+fn tag(tag: MarkupTag, children: Vec<MarkupNode>) -> MarkupNode {
+    MarkupNode::Tag {
+        tag,
+        subject_id: None,
+        children,
+    }
+}
+
+#[test]
+fn parses_nested_tags() {
+    assert_eq!(
+        parse_markup("The <kanji>one <reading>いち</reading></kanji> of it").unwrap(),
+        [
+            text("The "),
+            tag(
+                MarkupTag::Kanji,
+                vec![text("one "), tag(MarkupTag::Reading, vec![text("いち")])]
+            ),
+            text(" of it"),
+        ]
+    );
+    assert!(parse_markup("").unwrap().is_empty());
+}
+
+#[test]
+fn keeps_literal_angle_brackets() {
+    assert_eq!(
+        parse_markup("1 < 2, <> and <3").unwrap(),
+        [text("1 < 2, <> and <3")]
+    );
+}
+
+#[test]
+fn rejects_broken_markup() {
+    assert_eq!(
+        parse_markup("<kanji>one</radical>"),
+        Err(MarkupError::UnexpectedClose {
+            name: "radical".to_string(),
+            position: 10,
+        })
+    );
+    assert_eq!(
+        parse_markup("one</ja>"),
+        Err(MarkupError::UnexpectedClose {
+            name: "ja".to_string(),
+            position: 3,
+        })
+    );
+    assert_eq!(
+        parse_markup("a <meaning>b <ja>c</ja>"),
+        Err(MarkupError::Unclosed {
+            tag: MarkupTag::Meaning,
+            position: 2,
+        })
+    );
+    assert_eq!(
+        parse_markup("<b>bold</b>"),
+        Err(MarkupError::UnknownTag {
+            name: "b".to_string(),
+            position: 0,
+        })
+    );
+}
+
+#[test]
+fn resolves_references() {
+    let references = references();
+    let rendered = render_mnemonic(
+        "<radical>Ground</radical> and <radical>pistol</radical> make <kanji> ONE </kanji>, \
+         not <vocabulary>大人</vocabulary> or <reading>one</reading>",
+        &references,
+    )
+    .unwrap();
+    let ids = rendered
+        .nodes
+        .iter()
+        .filter_map(|x| match x {
+            MarkupNode::Tag { subject_id, .. } => Some(*subject_id),
+            MarkupNode::Text { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [Some(1), Some(8762), Some(440), Some(2560), None]);
+    assert_eq!(
+        references.lookup(MarkupTag::Vocabulary, "grown up"),
+        Some(2560)
+    );
+    assert_eq!(references.lookup(MarkupTag::Kanji, "ground"), None);
+}
+
+#[test]
+fn leaves_ambiguous_names_unresolved() {
+    let mut other = fixture("kanji");
+    other.id = 441;
+    let references = References::from_subjects(&[fixture("kanji"), other]);
+    assert_eq!(references.lookup(MarkupTag::Kanji, "one"), None);
+}
+
+#[test]
+fn renders_html_and_plain_text() {
+    let rendered = render_mnemonic("<ja>大人</ja> & <kanji>One</kanji>", &references()).unwrap();
+    assert_eq!(
+        rendered.html,
+        "<span class=\"mnemonic-ja\" lang=\"ja\">大人</span> &amp; \
+         <span class=\"mnemonic-kanji\" data-subject-id=\"440\">One</span>"
+    );
+    assert_eq!(rendered.plain_text, "大人 & One");
+}
@@ -0,0 +1,97 @@
+use japtools_core::{
+    furigana::{FuriganaSegment, SegmentKind},
+    normalize::{meaning_key, meaning_keys, reading_keys, vocabulary_reading_keys},
+    schema::VocabularyReading,
+};
+
+fn segment(text: &str, reading: &str, kind: SegmentKind) -> FuriganaSegment {
+    FuriganaSegment {
+        text: text.to_string(),
+        reading: reading.to_string(),
+        kind,
+    }
+}
+
+fn vocabulary_reading(reading: &str, furigana: Vec<FuriganaSegment>) -> VocabularyReading {
+    VocabularyReading {
+        accepted_answer: true,
+        primary: true,
+        reading: reading.to_string(),
+        pitch_accents: vec![],
+        furigana,
+        composition: None,
+    }
+}
+
+#[test]
+fn builds_kana_and_romaji_keys() {
+    assert_eq!(
+        reading_keys(["こう"]),
+        ["ko", "kou", "こう", "コウ"].map(String::from)
+    );
+    assert_eq!(
+        reading_keys([" しん ", "じ"]),
+        ["ji", "shin", "sin", "zi", "しん", "じ", "シン", "ジ"].map(String::from)
+    );
+    assert!(reading_keys(["きょう"]).contains(&"kyo".to_string()));
+    assert!(reading_keys(["おお"]).contains(&"o".to_string()));
+    assert!(reading_keys(["ゆう"]).contains(&"yu".to_string()));
+    assert!(reading_keys(["こんや"]).contains(&"kon'ya".to_string()));
+    assert!(reading_keys(["こんや"]).contains(&"konya".to_string()));
+    assert!(reading_keys([""]).is_empty());
+}
+
+#[test]
+fn keeps_long_vowels_across_morphemes() {
+    let school = vocabulary_reading(
+        "がっこう",
+        vec![
+            segment("学", "がっ", SegmentKind::Onyomi),
+            segment("校", "こう", SegmentKind::Onyomi),
+        ],
+    );
+    let think = vocabulary_reading(
+        "おもう",
+        vec![
+            segment("思", "おも", SegmentKind::Kunyomi),
+            segment("う", "う", SegmentKind::None),
+        ],
+    );
+    let chase = vocabulary_reading(
+        "おう",
+        vec![
+            segment("追", "お", SegmentKind::Kunyomi),
+            segment("う", "う", SegmentKind::None),
+        ],
+    );
+    let today = vocabulary_reading(
+        "きょう",
+        vec![segment("今日", "きょう", SegmentKind::Irregular)],
+    );
+
+    assert!(vocabulary_reading_keys(&[school]).contains(&"gakko".to_string()));
+    assert!(vocabulary_reading_keys(&[today]).contains(&"kyo".to_string()));
+    assert_eq!(
+        vocabulary_reading_keys(&[think]),
+        ["omou", "おもう", "オモウ"].map(String::from)
+    );
+    assert_eq!(
+        vocabulary_reading_keys(&[chase]),
+        ["ou", "おう", "オウ"].map(String::from)
+    );
+    // Without furigana there is nothing to tell the morphemes apart
+    assert!(
+        !vocabulary_reading_keys(&[vocabulary_reading("すう", vec![])]).contains(&"su".to_string())
+    );
+}
+
+#[test]
+fn normalizes_meanings() {
+    assert_eq!(meaning_key("  To Do (Something)! "), "to do something");
+    assert_eq!(meaning_key("Children’s Day"), "childrens day");
+    assert_eq!(meaning_key("Ice-Cream"), "ice cream");
+    assert_eq!(
+        meaning_keys(["One", "one", "Person's", "", "?"]),
+        ["one", "persons"].map(String::from)
+    );
+}
@@ -0,0 +1,21 @@
+use japtools_core::pitch::{pitch_pattern, PitchPattern};
+
+#[test]
+fn classifies_downsteps() {
+    assert_eq!(pitch_pattern("がっこう", 0), PitchPattern::Heiban);
+    assert_eq!(pitch_pattern("きょうしつ", 0), PitchPattern::Heiban);
+    assert_eq!(pitch_pattern("きょう", 1), PitchPattern::Atamadaka);
+    assert_eq!(pitch_pattern("あなた", 2), PitchPattern::Nakadaka);
+    assert_eq!(pitch_pattern("コーヒー", 3), PitchPattern::Nakadaka);
+    assert_eq!(pitch_pattern("やま", 2), PitchPattern::Odaka);
+    assert_eq!(pitch_pattern("おとうと", 4), PitchPattern::Odaka);
+}
+
+#[test]
+fn counts_small_kana_with_the_previous_mora() {
+    // ひゃ く え ん is four morae, so a downstep after the fourth is odaka
+    assert_eq!(pitch_pattern("ひゃくえん", 2), PitchPattern::Nakadaka);
+    assert_eq!(pitch_pattern("ひゃくえん", 4), PitchPattern::Odaka);
+    assert_eq!(pitch_pattern("しゅっぱつ", 3), PitchPattern::Nakadaka);
+    assert_eq!(pitch_pattern("しゅっぱつ", 4), PitchPattern::Odaka);
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use japtools_core::{
+    grading::{AnswerType, Verdict},
+    review_log::{group_reviews, replay_history, ReviewLogEntry},
+    schema::ReadingType,
+    srs::{Scheduler, SpacedRepetitionSystem},
+};
+
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, 12, day, hour, minute, 0)
+        .unwrap()
+}
+
+fn answer(subject_id: u32, reviewed_at: DateTime<Utc>, verdict: Verdict) -> ReviewLogEntry {
+    ReviewLogEntry {
+        subject_id,
+        reviewed_at,
+        answer_type: AnswerType::Meaning,
+        given_answer: "one".to_string(),
+        verdict,
+        previous_level: 1,
+        new_level: 1,
+        response_time_ms: 2500,
+    }
+}
+
+#[test]
+fn groups_answers_into_reviews() {
+    let entries = [
+        answer(440, at(1, 12, 5), Verdict::Wrong),
+        answer(440, at(1, 12, 0), Verdict::Correct),
+        answer(440, at(1, 12, 7), Verdict::Close),
+        answer(
+            440,
+            at(1, 12, 8),
+            Verdict::WrongReadingType {
+                expected: ReadingType::Onyomi,
+            },
+        ),
+        answer(440, at(1, 20, 0), Verdict::Correct),
+        answer(440, at(1, 20, 1), Verdict::Correct),
+    ];
+    let reviews = group_reviews(&entries);
+    assert_eq!(reviews.len(), 2);
+    assert_eq!(reviews[0].reviewed_at, at(1, 12, 8));
+    assert_eq!(reviews[0].incorrect_answers, 1);
+    assert!(reviews[0].close);
+    assert_eq!(reviews[1].reviewed_at, at(1, 20, 1));
+    assert_eq!(reviews[1].incorrect_answers, 0);
+    assert!(group_reviews(&[]).is_empty());
+}
+
+#[test]
+fn replays_history_per_subject() {
+    let scheduler = Scheduler::Fixed(SpacedRepetitionSystem::wanikani());
+    let entries = [
+        answer(440, at(1, 8, 0), Verdict::Correct),
+        answer(441, at(1, 8, 1), Verdict::Wrong),
+        answer(441, at(1, 8, 2), Verdict::Correct),
+        answer(440, at(1, 16, 0), Verdict::Correct),
+        answer(440, at(2, 16, 0), Verdict::Wrong),
+    ];
+    let progress = replay_history(&scheduler, &entries);
+    assert_eq!(
+        progress.iter().map(|x| x.subject_id).collect::<Vec<_>>(),
+        [440, 441]
+    );
+
+    // 1 -> 2 -> 3 -> 2
+    assert_eq!(progress[0].schedule.stage, 2);
+    assert_eq!(progress[0].schedule.next_review, Some(at(3, 0, 0)));
+    assert_eq!(progress[0].last_level_change, Some(at(2, 16, 0)));
+
+    // A wrong answer at the starting stage keeps it there
+    assert_eq!(progress[1].schedule.stage, 1);
+    assert_eq!(progress[1].schedule.next_review, Some(at(1, 12, 0)));
+    assert_eq!(progress[1].last_level_change, None);
+}
+
+#[test]
+fn replayed_stages_match_logged_levels() {
+    let scheduler = Scheduler::Fixed(SpacedRepetitionSystem::wanikani());
+    let logged = |reviewed_at, verdict, previous_level, new_level| ReviewLogEntry {
+        previous_level,
+        new_level,
+        ..answer(440, reviewed_at, verdict)
+    };
+    // The level changes with the last answer of each review
+    let entries = [
+        logged(at(1, 8, 0), Verdict::Correct, 1, 1),
+        logged(at(1, 8, 1), Verdict::Correct, 1, 2),
+        logged(at(1, 16, 0), Verdict::Wrong, 2, 2),
+        logged(at(1, 16, 2), Verdict::Correct, 2, 1),
+        logged(at(1, 20, 30), Verdict::Correct, 1, 1),
+        logged(at(1, 20, 31), Verdict::Correct, 1, 2),
+    ];
+    let reviews = group_reviews(&entries);
+    let schedules = scheduler.replay_steps(at(1, 8, 0), &reviews);
+    assert_eq!(schedules.len(), reviews.len() + 1);
+    for (review, schedule) in reviews.iter().zip(&schedules[1..]) {
+        let entry = entries
+            .iter()
+            .find(|x| x.reviewed_at == review.reviewed_at)
+            .unwrap();
+        assert_eq!(schedule.stage, entry.new_level, "{}", review.reviewed_at);
+    }
+
+    let progress = replay_history(&scheduler, &entries);
+    assert_eq!(progress[0].schedule.stage, 2);
+    assert_eq!(progress[0].last_level_change, Some(at(1, 20, 31)));
+}
+
+#[test]
+fn serializes_entries() {
+    let entry = answer(440, at(1, 8, 0), Verdict::Close);
+    let json = serde_json::to_value(&entry).unwrap();
+    assert_eq!(json["answer_type"], "meaning");
+    assert_eq!(json["verdict"], "close");
+    assert_eq!(json["reviewed_at"], "2023-12-01T08:00:00Z");
+    assert_eq!(
+        serde_json::from_value::<ReviewLogEntry>(json).unwrap(),
+        entry
+    );
+}
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+
+use japtools_core::ime::{to_kana, RomajiInput};
+
+// apps/web/tests/romaji_input.test.ts runs WanaKana over the same table, so both agree
+const TABLE: &str = include_str!("fixtures/romaji_input.json");
+
+#[derive(Deserialize)]
+struct Case {
+    input: String,
+    output: String,
+    #[serde(default)]
+    ime_mode: bool,
+}
+
+#[test]
+fn converts_shared_table() {
+    let cases: Vec<Case> = serde_json::from_str(TABLE).unwrap();
+    for case in cases {
+        assert_eq!(
+            to_kana(&case.input, case.ime_mode),
+            case.output,
+            "{} (ime mode {})",
+            case.input,
+            case.ime_mode
+        );
+    }
+}
+
+#[test]
+fn converts_each_keystroke() {
+    let mut input = RomajiInput::new();
+    let mut shown = Vec::new();
+    for c in "kyounn".chars() {
+        input.push(c);
+        shown.push(input.text());
+    }
+    assert_eq!(shown, ["k", "ky", "きょ", "きょう", "きょうn", "きょうん"]);
+    assert!(!input.is_pending());
+}
+
+#[test]
+fn backspace_removes_pending_romaji_first() {
+    let mut input = RomajiInput::new();
+    input.push_str("kas");
+    input.backspace();
+    assert_eq!(input.text(), "か");
+    input.backspace();
+    assert_eq!(input.text(), "");
+    input.push_str("shin");
+    assert!(input.is_pending());
+    input.finish();
+    assert_eq!(input.text(), "しん");
+}
+
+#[test]
+fn reconverting_ime_output_is_stable() {
+    let cases: Vec<Case> = serde_json::from_str(TABLE).unwrap();
+    for case in cases.iter().filter(|x| x.ime_mode) {
+        let once = to_kana(&case.input, true);
+        assert_eq!(to_kana(&once, true), once, "{}", case.input);
+    }
+}
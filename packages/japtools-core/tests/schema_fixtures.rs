@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use japtools_core::schema::{CharacterImageMetadata, ReadingType, SubjectData, SubjectDataOuter};
+
+// Trimmed WaniKani API payloads, one per subject variant and metadata shape
+const FIXTURES: &[(&str, &str)] = &[
+    ("radical", include_str!("fixtures/subjects/radical.json")),
+    (
+        "radical_image_only",
+        include_str!("fixtures/subjects/radical_image_only.json"),
+    ),
+    ("kanji", include_str!("fixtures/subjects/kanji.json")),
+    (
+        "kanji_hidden",
+        include_str!("fixtures/subjects/kanji_hidden.json"),
+    ),
+    (
+        "vocabulary",
+        include_str!("fixtures/subjects/vocabulary.json"),
+    ),
+    (
+        "vocabulary_readings",
+        include_str!("fixtures/subjects/vocabulary_readings.json"),
+    ),
+    (
+        "kana_vocabulary",
+        include_str!("fixtures/subjects/kana_vocabulary.json"),
+    ),
+];
+
+fn fixture(name: &str) -> SubjectDataOuter {
+    let (_, text) = FIXTURES.iter().find(|(x, _)| *x == name).unwrap();
+    serde_json::from_str(text).unwrap_or_else(|e| panic!("{} does not deserialize: {}", name, e))
+}
+
+#[test]
+fn fixtures_round_trip() {
+    for (name, text) in FIXTURES {
+        let raw: Value = serde_json::from_str(text).unwrap();
+        let subject: SubjectDataOuter = serde_json::from_value(raw.clone()).unwrap();
+        let serialized = serde_json::to_value(&subject).unwrap();
+        assert_eq!(serialized, raw, "{} changed when serialized", name);
+
+        let again: SubjectDataOuter = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&again).unwrap(),
+            serialized,
+            "{} is not stable across a second round trip",
+            name
+        );
+    }
+}
+
+#[test]
+fn fixtures_match_snapshots() {
+    for (name, _) in FIXTURES {
+        insta::assert_debug_snapshot!(*name, fixture(name));
+    }
+}
+
+#[test]
+fn cache_map_round_trip() {
+    let map = FIXTURES
+        .iter()
+        .map(|(name, _)| fixture(name))
+        .map(|subject| (subject.id, subject))
+        .collect::<HashMap<u32, SubjectDataOuter>>();
+    let cache = serde_json::to_string(&map).unwrap();
+    let loaded: HashMap<u32, SubjectDataOuter> = serde_json::from_str(&cache).unwrap();
+
+    assert_eq!(loaded.len(), FIXTURES.len());
+    for (id, subject) in &map {
+        assert_eq!(
+            serde_json::to_value(&loaded[id]).unwrap(),
+            serde_json::to_value(subject).unwrap()
+        );
+    }
+}
+
+#[test]
+fn fixtures_cover_every_variant() {
+    let subjects = FIXTURES
+        .iter()
+        .map(|(name, _)| fixture(name))
+        .collect::<Vec<_>>();
+    let has = |f: fn(&SubjectData) -> bool| subjects.iter().any(|x| f(&x.data));
+    assert!(has(|x| matches!(x, SubjectData::Radical(_))));
+    assert!(has(|x| matches!(x, SubjectData::Kanji(_))));
+    assert!(has(|x| matches!(x, SubjectData::Vocabulary(_))));
+    assert!(has(|x| matches!(x, SubjectData::KanaVocabulary(_))));
+
+    let images = subjects
+        .iter()
+        .filter_map(|x| match &x.data {
+            SubjectData::Radical(data) => Some(&data.character_images),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    assert!(images.iter().any(|x| matches!(
+        x.metadata,
+        CharacterImageMetadata::Svg(ref svg) if svg.inline_styles
+    )));
+    assert!(images.iter().any(|x| matches!(
+        x.metadata,
+        CharacterImageMetadata::Svg(ref svg) if !svg.inline_styles
+    )));
+    assert!(images
+        .iter()
+        .any(|x| matches!(x.metadata, CharacterImageMetadata::Png(_))));
+
+    let readings = subjects
+        .iter()
+        .filter_map(|x| match &x.data {
+            SubjectData::Kanji(data) => Some(&data.readings),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    assert!(readings
+        .iter()
+        .any(|x| matches!(x.reading_type, ReadingType::Onyomi)));
+    assert!(readings
+        .iter()
+        .any(|x| matches!(x.reading_type, ReadingType::Kunyomi)));
+    assert!(readings
+        .iter()
+        .any(|x| matches!(x.reading_type, ReadingType::Nanori)));
+
+    let audio_types = subjects
+        .iter()
+        .filter_map(|x| match &x.data {
+            SubjectData::Vocabulary(data) => Some(&data.pronunciation_audios),
+            _ => None,
+        })
+        .flatten()
+        .map(|x| x.content_type.as_str())
+        .collect::<Vec<_>>();
+    assert!(audio_types.contains(&"audio/mpeg"));
+    assert!(audio_types.contains(&"audio/webm"));
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::Value;
+
+use japtools_core::{
+    grading::Verdict,
+    srs::{Fsrs, Rating, Review, Schedule, Scheduler, SpacedRepetitionSystem},
+};
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, 12, 1, hour, minute, 0).unwrap()
+}
+
+fn review(reviewed_at: DateTime<Utc>, incorrect_answers: u32) -> Review {
+    Review {
+        reviewed_at,
+        incorrect_answers,
+        close: false,
+    }
+}
+
+#[test]
+fn reads_wanikani_payload() {
+    let payload: Value =
+        serde_json::from_str(include_str!("fixtures/spaced_repetition_system.json")).unwrap();
+    let system: SpacedRepetitionSystem = serde_json::from_value(payload["data"].clone()).unwrap();
+    assert_eq!(system.name, "Wanikani Short");
+    assert_eq!(system.interval(1), Some(Duration::hours(2)));
+    assert_eq!(system.interval(8), Some(Duration::hours(2879)));
+    assert_eq!(system.interval(9), None);
+    assert_eq!(system.stages.len(), 10);
+}
+
+#[test]
+fn moves_through_fixed_stages() {
+    let system = SpacedRepetitionSystem::wanikani();
+    // (stage, incorrect answers, next stage)
+    for (stage, incorrect, next) in [
+        (1, 0, 2),
+        (4, 0, 5),
+        (8, 0, 9),
+        (9, 0, 9),
+        (1, 1, 1),
+        (3, 1, 2),
+        (3, 2, 2),
+        (3, 3, 1),
+        (5, 1, 3),
+        (6, 2, 4),
+        (8, 3, 4),
+        (8, 10, 1),
+    ] {
+        assert_eq!(
+            system.next_stage(stage, incorrect),
+            next,
+            "stage {} with {} wrong",
+            stage,
+            incorrect
+        );
+    }
+}
+
+#[test]
+fn rounds_fixed_reviews_down_to_the_hour() {
+    let system = SpacedRepetitionSystem::wanikani();
+    assert_eq!(system.next_review(1, at(10, 35)), Some(at(14, 0)));
+    assert_eq!(system.next_review(3, at(0, 59)), Some(at(23, 0)));
+    assert_eq!(system.next_review(9, at(10, 35)), None);
+}
+
+#[test]
+fn replays_fixed_history() {
+    let scheduler = Scheduler::Fixed(SpacedRepetitionSystem::wanikani());
+    assert_eq!(
+        scheduler.replay(at(8, 10), &[]),
+        Schedule {
+            stage: 1,
+            next_review: Some(at(12, 0)),
+            memory: None,
+        }
+    );
+    let history = [
+        review(at(12, 5), 0),
+        review(at(20, 30), 0),
+        review(at(21, 0) + Duration::days(1), 1),
+    ];
+    let schedule = scheduler.replay(at(8, 10), &history);
+    assert_eq!(schedule.stage, 2);
+    assert_eq!(
+        schedule.next_review,
+        Some(at(21, 0) + Duration::days(1) + Duration::hours(8))
+    );
+
+    // Order of the history does not matter
+    let mut shuffled = history;
+    shuffled.reverse();
+    assert_eq!(scheduler.replay(at(8, 10), &shuffled), schedule);
+
+    let burned = (1..=8)
+        .map(|x| review(at(0, 0) + Duration::days(x * 200), 0))
+        .collect::<Vec<_>>();
+    let schedule = scheduler.replay(at(0, 0), &burned);
+    assert_eq!(schedule.stage, 9);
+    assert_eq!(schedule.next_review, None);
+}
+
+#[test]
+fn rates_reviews_from_verdicts() {
+    let cases = [
+        (vec![Verdict::Correct, Verdict::Correct], 0, Rating::Good),
+        (vec![Verdict::Close, Verdict::Correct], 0, Rating::Hard),
+        (
+            vec![Verdict::Wrong, Verdict::Blacklisted, Verdict::Correct],
+            2,
+            Rating::Again,
+        ),
+        (
+            vec![Verdict::OtherReading, Verdict::Correct],
+            1,
+            Rating::Again,
+        ),
+    ];
+    for (verdicts, incorrect, rating) in cases {
+        let review = Review::from_verdicts(at(9, 0), &verdicts);
+        assert_eq!(review.incorrect_answers, incorrect, "{:?}", verdicts);
+        assert_eq!(review.rating(), rating, "{:?}", verdicts);
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn fsrs_follows_reference_values() {
+    let model = Fsrs::default();
+    let state = model.initial_state(Rating::Again);
+    assert_close(state.stability, 0.4872);
+    assert_close(state.difficulty, 7.6214);
+    assert_close(model.initial_state(Rating::Good).stability, 3.7145);
+    assert_close(model.initial_state(Rating::Easy).difficulty, 3.9320);
+
+    let state = model.next_state(&state, 0.5, Rating::Good);
+    assert_close(state.stability, 1.5518517125988223);
+    assert_close(state.difficulty, 7.5451524);
+    let state = model.next_state(&state, 2.0, Rating::Good);
+    assert_close(state.stability, 5.174813643000271);
+    assert_close(state.difficulty, 7.4712684756);
+
+    // At 90% desired retention the interval is the stability
+    assert_close(model.interval_days(&state), state.stability);
+    assert_close(model.retrievability(&state, state.stability), 0.9);
+}
+
+#[test]
+fn fsrs_rewards_late_recall_and_punishes_lapses() {
+    let model = Fsrs::default();
+    let state = model.initial_state(Rating::Good);
+    let on_time = model.next_state(&state, 3.0, Rating::Good);
+    let late = model.next_state(&state, 9.0, Rating::Good);
+    let hard = model.next_state(&state, 3.0, Rating::Hard);
+    let lapse = model.next_state(&state, 3.0, Rating::Again);
+    assert!(late.stability > on_time.stability);
+    assert!(hard.stability < on_time.stability);
+    assert!(lapse.stability < state.stability);
+    assert!(lapse.difficulty > state.difficulty);
+    assert!(hard.difficulty > on_time.difficulty);
+}
+
+#[test]
+fn replays_adaptive_history() {
+    let scheduler = Scheduler::Adaptive {
+        model: Fsrs::default(),
+        system: SpacedRepetitionSystem::wanikani(),
+    };
+    let first = scheduler.replay(at(8, 0), &[]);
+    let memory = first.memory.unwrap();
+    assert_close(memory.stability, 0.4872);
+    assert_eq!(first.stage, 2);
+    assert_eq!(
+        first.next_review,
+        Some(at(8, 0) + Duration::seconds((0.4872 * 86400.0) as i64))
+    );
+
+    let history = [
+        review(at(20, 0), 0),
+        review(at(20, 0) + Duration::days(2), 0),
+    ];
+    let schedule = scheduler.replay(at(8, 0), &history);
+    let memory = schedule.memory.unwrap();
+    assert_close(memory.stability, 5.174813643000271);
+    assert_eq!(schedule.stage, 4);
+    assert!(schedule.next_review.unwrap() > history[1].reviewed_at + Duration::days(5));
+}
@@ -0,0 +1,96 @@
+use japtools_core::tokenize::{Dictionary, SentenceToken};
+
+fn dictionary() -> Dictionary {
+    let mut dictionary = Dictionary::default();
+    for (characters, subject_id) in [("書", 10), ("行", 11), ("食", 12), ("大", 13), ("人", 14)]
+    {
+        dictionary.add_kanji(characters, subject_id);
+    }
+    for (characters, subject_id) in [
+        ("書く", 20),
+        ("行く", 21),
+        ("食べる", 22),
+        ("大きい", 23),
+        ("人", 24),
+        ("大人", 25),
+    ] {
+        dictionary.add_vocabulary(characters, subject_id);
+    }
+    dictionary
+}
+
+fn tokens(expected: &[(&str, Option<u32>)]) -> Vec<SentenceToken> {
+    expected
+        .iter()
+        .map(|(text, subject_id)| SentenceToken {
+            text: text.to_string(),
+            subject_id: *subject_id,
+        })
+        .collect()
+}
+
+#[test]
+fn links_godan_conjugations() {
+    let dictionary = dictionary();
+    for text in ["書く", "書いた", "書きます", "書かない", "書けば"] {
+        assert_eq!(dictionary.tokenize(text), tokens(&[(text, Some(20))]));
+    }
+    for text in ["行った", "行って", "行きました"] {
+        assert_eq!(dictionary.tokenize(text), tokens(&[(text, Some(21))]));
+    }
+}
+
+#[test]
+fn links_ichidan_conjugations() {
+    let dictionary = dictionary();
+    for text in [
+        "食べる",
+        "食べない",
+        "食べました",
+        "食べて",
+        "食べたい",
+        "食べられる",
+    ] {
+        assert_eq!(dictionary.tokenize(text), tokens(&[(text, Some(22))]));
+    }
+}
+
+#[test]
+fn links_adjective_conjugations() {
+    let dictionary = dictionary();
+    for text in ["大きい", "大きくない", "大きかった", "大きければ"] {
+        assert_eq!(dictionary.tokenize(text), tokens(&[(text, Some(23))]));
+    }
+}
+
+#[test]
+fn prefers_longer_matches_then_vocabulary() {
+    let dictionary = dictionary();
+    assert_eq!(dictionary.tokenize("大人"), tokens(&[("大人", Some(25))]));
+    assert_eq!(dictionary.tokenize("人"), tokens(&[("人", Some(24))]));
+    // A stem without a conjugation after it is only the kanji
+    assert_eq!(
+        dictionary.tokenize("書道"),
+        tokens(&[("書", Some(10)), ("道", None)])
+    );
+}
+
+#[test]
+fn keeps_unknown_text() {
+    let dictionary = dictionary();
+    assert_eq!(
+        dictionary.tokenize("これは大人が書いた本です。"),
+        tokens(&[
+            ("これは", None),
+            ("大人", Some(25)),
+            ("が", None),
+            ("書いた", Some(20)),
+            ("本です。", None),
+        ])
+    );
+    assert!(dictionary.tokenize("").is_empty());
+    assert!(Dictionary::default()
+        .tokenize("本")
+        .iter()
+        .all(|x| x.subject_id.is_none()));
+}
@@ -0,0 +1,9 @@
+-- AlterTable
+ALTER TABLE "SubjectIndex" ADD COLUMN     "meaning_keys" STRING[];
+ALTER TABLE "SubjectIndex" ADD COLUMN     "reading_keys" STRING[];
+
+-- CreateIndex
+CREATE INDEX "SubjectIndex_reading_keys_idx" ON "SubjectIndex" USING GIN ("reading_keys");
+
+-- CreateIndex
+CREATE INDEX "SubjectIndex_meaning_keys_idx" ON "SubjectIndex" USING GIN ("meaning_keys");
@@ -0,0 +1,3 @@
+-- AlterTable
+ALTER TABLE "KanjiSubject" ADD COLUMN     "stroke_count" INT4;
+ALTER TABLE "KanjiSubject" ADD COLUMN     "stroke_paths" STRING[];
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "VocabularyReading" ADD COLUMN     "pitch_accents" INT4[];
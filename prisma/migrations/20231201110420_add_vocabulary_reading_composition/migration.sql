@@ -0,0 +1,5 @@
+-- CreateEnum
+CREATE TYPE "ReadingComposition" AS ENUM ('REGULAR', 'SOUND_CHANGE', 'JUKUJIKUN');
+
+-- AlterTable
+ALTER TABLE "VocabularyReading" ADD COLUMN     "composition" "ReadingComposition";
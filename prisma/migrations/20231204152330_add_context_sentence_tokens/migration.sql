@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "ContextSentence" ADD COLUMN     "tokens" JSONB;
@@ -0,0 +1,19 @@
+-- CreateTable
+CREATE TABLE "ExampleSentence" (
+    "id" STRING NOT NULL,
+    "tatoeba_id" INT4 NOT NULL,
+    "en" STRING NOT NULL,
+    "ja" STRING NOT NULL,
+    "rank" INT4 NOT NULL,
+    "tokens" JSONB,
+    "kanjiSubjectId" INT4,
+    "vocabularySubjectId" INT4,
+
+    CONSTRAINT "ExampleSentence_pkey" PRIMARY KEY ("id")
+);
+
+-- AddForeignKey
+ALTER TABLE "ExampleSentence" ADD CONSTRAINT "ExampleSentence_kanjiSubjectId_fkey" FOREIGN KEY ("kanjiSubjectId") REFERENCES "KanjiSubject"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "ExampleSentence" ADD CONSTRAINT "ExampleSentence_vocabularySubjectId_fkey" FOREIGN KEY ("vocabularySubjectId") REFERENCES "VocabularySubject"("id") ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,31 @@
+-- CreateEnum
+CREATE TYPE "AnswerType" AS ENUM ('MEANING', 'READING');
+
+-- CreateEnum
+CREATE TYPE "ReviewVerdict" AS ENUM ('CORRECT', 'CLOSE', 'OTHER_READING', 'WRONG_READING_TYPE', 'BLACKLISTED', 'WRONG');
+
+-- CreateTable
+CREATE TABLE "ReviewLog" (
+    "id" STRING NOT NULL,
+    "user_id" STRING NOT NULL,
+    "subject_id" INT4 NOT NULL,
+    "reviewed_at" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "answer_type" "AnswerType" NOT NULL,
+    "given_answer" STRING NOT NULL,
+    "verdict" "ReviewVerdict" NOT NULL,
+    "expected_reading_type" "ReadingType",
+    "previous_level" INT4 NOT NULL,
+    "new_level" INT4 NOT NULL,
+    "response_time_ms" INT4 NOT NULL,
+
+    CONSTRAINT "ReviewLog_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE INDEX "ReviewLog_user_id_subject_id_reviewed_at_idx" ON "ReviewLog"("user_id", "subject_id", "reviewed_at");
+
+-- AddForeignKey
+ALTER TABLE "ReviewLog" ADD CONSTRAINT "ReviewLog_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "ReviewLog" ADD CONSTRAINT "ReviewLog_subject_id_fkey" FOREIGN KEY ("subject_id") REFERENCES "SubjectIndex"("subjectId") ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,20 @@
+-- CreateEnum
+CREATE TYPE "MediaKind" AS ENUM ('CHARACTER_IMAGE', 'PRONUNCIATION_AUDIO');
+
+-- CreateTable
+CREATE TABLE "SubjectMedia" (
+    "id" STRING NOT NULL,
+    "subject_id" INT4 NOT NULL,
+    "kind" "MediaKind" NOT NULL,
+    "url" STRING NOT NULL,
+    "content_type" STRING NOT NULL,
+    "metadata" JSONB,
+
+    CONSTRAINT "SubjectMedia_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE INDEX "SubjectMedia_subject_id_idx" ON "SubjectMedia"("subject_id");
+
+-- AddForeignKey
+ALTER TABLE "SubjectMedia" ADD CONSTRAINT "SubjectMedia_subject_id_fkey" FOREIGN KEY ("subject_id") REFERENCES "SubjectIndex"("subjectId") ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,5 @@
+-- CreateEnum
+CREATE TYPE "PitchPattern" AS ENUM ('HEIBAN', 'ATAMADAKA', 'NAKADAKA', 'ODAKA');
+
+-- AlterTable
+ALTER TABLE "VocabularyReading" ADD COLUMN     "pitch_patterns" "PitchPattern"[];
@@ -0,0 +1,5 @@
+-- DropForeignKey
+ALTER TABLE "ReviewLog" DROP CONSTRAINT "ReviewLog_subject_id_fkey";
+
+-- AddForeignKey
+ALTER TABLE "ReviewLog" ADD CONSTRAINT "ReviewLog_subject_id_fkey" FOREIGN KEY ("subject_id") REFERENCES "SubjectIndex"("subjectId") ON DELETE RESTRICT ON UPDATE CASCADE;